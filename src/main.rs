@@ -13,7 +13,11 @@ pub use maat_graphics::cgmath;
 mod modules;
 
 use crate::modules::scenes::Scene;
+use crate::modules::scenes::SceneTransition;
 use crate::modules::scenes::LoadScreen;
+use crate::modules::preferences;
+use crate::modules::scripting;
+use crate::modules::scene_browser;
 
 use maat_graphics::graphics::CoreRender;
 use maat_graphics::CoreMaat;
@@ -48,8 +52,10 @@ fn fps_overlay(draw_calls: &mut Vec<DrawCall>, dimensions: Vector2<f32>, fps: f6
 
 fn main() {
   let mut lua = Lua::new();
+  let scripting_error = scripting::install(&mut lua);
   
   let mut imgui = ImGui::init();
+  imgui.set_ini_filename(Some(ImString::new(preferences::layout_location())));
   let mut graphics = CoreMaat::new("Maat Editor".to_string(), (MAJOR) << 22 | (MINOR) << 12 | (PATCH), 1280.0, 1080.0, true).use_imgui(&mut imgui);
   
   graphics.preload_font(String::from("Arial"),
@@ -58,13 +64,11 @@ fn main() {
   graphics.preload_texture(String::from("Logo"), 
                            String::from("./resources/Textures/Logo.png"));
   
-  graphics.add_model(String::from("Axis"), String::from("./Models/Axis.glb"));
-  
   graphics.load_shaders();
   
   graphics.set_clear_colour(0.2, 0.2, 0.2, 1.0);
   
-  let mut game: Box<Scene> = Box::new(LoadScreen::new());
+  let mut scenes: Vec<Box<Scene>> = vec![Box::new(LoadScreen::new(scripting_error))];
   
   let mut draw_calls: Vec<DrawCall> = Vec::with_capacity(100);
   
@@ -93,6 +97,8 @@ fn main() {
     
     dimensions = graphics.get_virtual_dimensions();
     
+    let game = scenes.last_mut().unwrap();
+    
     let models = game.get_models_to_unload();
     for reference in &models {
       draw_calls.push(DrawCall::unload_model(reference.to_string()));
@@ -100,16 +106,25 @@ fn main() {
     
     let models = game.get_models_to_load();
     for (reference, location) in &models {
-      graphics.add_model(reference.to_string(), location.to_string());
+      graphics.add_model(reference.to_string(), scene_browser::project_file(location));
       draw_calls.push(DrawCall::load_model(reference.to_string()));
     }
     
-    if game.scene_finished() {
-      game = game.future_scene(dimensions);
+    let textures = game.get_textures_to_load();
+    for (reference, location) in &textures {
+      graphics.add_texture(reference.to_string(), location.to_string());
+      draw_calls.push(DrawCall::load_texture(reference.to_string()));
+    }
+    
+    let fonts = game.get_fonts_to_load();
+    for (reference, location, font) in &fonts {
+      graphics.add_font(reference.to_string(), location.to_string(), font);
+      draw_calls.push(DrawCall::load_font(reference.to_string()));
     }
     
     game.set_window_dimensions(dimensions);
     
+    imgui.set_font_global_scale(game.data().ui_scale);
     graphics.prepare_imgui_ui(Some(&mut imgui));
     imgui_time = imgui.io_mut().update_delta_time(imgui_time);
     let ui = imgui.frame();
@@ -126,11 +141,16 @@ fn main() {
     
     draw_calls.clear();
     
-    game.reset_scroll_value();
-    for (reference, size) in &model_details {
-      game.add_model_size(reference.to_string(), *size);
+    game.reset_frame_input();
+    // Scenes further down the stack still need to know about loaded models
+    for scene in &mut scenes {
+      for (reference, size) in &model_details {
+        scene.add_model_size(reference.to_string(), *size);
+      }
     }
     
+    let game = scenes.last_mut().unwrap();
+    
     let events = graphics.get_events(Some(&mut imgui));
     let mouse_pos = graphics.get_mouse_position();
     
@@ -154,8 +174,31 @@ fn main() {
       }
     }
     
+    if let Some(transition) = game.take_transition() {
+      match transition {
+        SceneTransition::Push(scene) => {
+          scenes.push(scene);
+        },
+        SceneTransition::Pop => {
+          scenes.pop();
+        },
+        SceneTransition::Replace(scene) => {
+          scenes.pop();
+          scenes.push(scene);
+        },
+      }
+    }
+    
+    if scenes.len() == 0 {
+      done = true;
+    }
+    
     if done { break; }
   }
   
+  for scene in &mut scenes {
+    scene.closing();
+  }
+  
   println!("Game Loop ended");
 }
@@ -0,0 +1,79 @@
+use crate::modules::placement::BoundingBox;
+
+use crate::cgmath::Vector3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AlignAnchor {
+  Min,
+  Centre,
+  Max,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AlignAction {
+  Align(AlignAnchor),
+  Distribute,
+  Mirror,
+}
+
+pub fn selection_bounds(bounds: &Vec<BoundingBox>) -> Option<BoundingBox> {
+  if bounds.len() == 0 {
+    return None;
+  }
+
+  let mut total = bounds[0];
+  for object in bounds {
+    for axis in 0..3 {
+      total.min[axis] = total.min[axis].min(object.min[axis]);
+      total.max[axis] = total.max[axis].max(object.max[axis]);
+    }
+  }
+
+  Some(total)
+}
+
+// How far each object has to move along axis so they all line up on the anchor
+pub fn align_offsets(bounds: &Vec<BoundingBox>, axis: usize, anchor: AlignAnchor) -> Vec<f32> {
+  let total = match selection_bounds(bounds) {
+    Some(total) => total,
+    None => return Vec::new(),
+  };
+
+  bounds.iter().map(|object| {
+    match anchor {
+      AlignAnchor::Min => total.min[axis] - object.min[axis],
+      AlignAnchor::Centre => total.centre()[axis] - object.centre()[axis],
+      AlignAnchor::Max => total.max[axis] - object.max[axis],
+    }
+  }).collect()
+}
+
+// Offsets that space the centres evenly, the two outermost objects stay put
+pub fn distribute_offsets(bounds: &Vec<BoundingBox>, axis: usize) -> Vec<f32> {
+  let mut offsets = vec![0.0; bounds.len()];
+  if bounds.len() < 3 {
+    return offsets;
+  }
+
+  let mut order = (0..bounds.len()).collect::<Vec<usize>>();
+  order.sort_by(|a, b| {
+    bounds[*a].centre()[axis].partial_cmp(&bounds[*b].centre()[axis]).unwrap_or(std::cmp::Ordering::Equal)
+  });
+
+  let first = bounds[order[0]].centre()[axis];
+  let last = bounds[order[order.len()-1]].centre()[axis];
+  let step = (last - first) / (order.len()-1) as f32;
+
+  for (i, idx) in order.iter().enumerate() {
+    offsets[*idx] = first + step*i as f32 - bounds[*idx].centre()[axis];
+  }
+
+  offsets
+}
+
+// Reflects across the plane perpendicular to axis at the coordinate plane
+pub fn mirror_position(position: Vector3<f32>, axis: usize, plane: f32) -> Vector3<f32> {
+  let mut mirrored = position;
+  mirrored[axis] = 2.0*plane - position[axis];
+  mirrored
+}
@@ -0,0 +1,140 @@
+use maat_graphics::imgui::*;
+
+use crate::cgmath::{Vector2, Vector3, Quaternion, Matrix3, Rotation3, Deg, Rad};
+
+use crate::modules::rotation;
+use crate::modules::rotation::EulerOrder;
+
+// Stops a typo in the counts from freezing the editor
+const MAX_ARRAY_COPIES: usize = 1000;
+
+pub const ARRAY_LINEAR: i32 = 0;
+pub const ARRAY_GRID: i32 = 1;
+pub const ARRAY_RADIAL: i32 = 2;
+
+// Turns orientation around the world Y axis so the models +Z points from pivot
+// towards position, the tilt around X and Z is kept
+fn facing_away_from(pivot: Vector3<f32>, position: Vector3<f32>, orientation: Quaternion<f32>) -> Quaternion<f32> {
+  let offset = position - pivot;
+  if offset.x.abs() < 0.00001 && offset.z.abs() < 0.00001 {
+    return orientation;
+  }
+
+  // XZY applies the turn around Y last so it is the heading in world space
+  let mut euler = rotation::euler_from_quaternion(orientation, EulerOrder::XZY);
+  euler.y = Deg::from(Rad(offset.x.atan2(offset.z))).0;
+  rotation::quaternion_from_euler(euler, EulerOrder::XZY)
+}
+
+#[derive(Clone)]
+pub struct ArrayTool {
+  pub mode: i32,
+  pub count: i32,
+  pub offset: [f32; 3],
+  pub grid_counts: [i32; 3],
+  pub grid_spacing: [f32; 3],
+  pub pivot: [f32; 3],
+  pub step_degrees: f32,
+  pub face_outward: bool,
+}
+
+impl ArrayTool {
+  pub fn new() -> ArrayTool {
+    ArrayTool {
+      mode: ARRAY_LINEAR,
+      count: 5,
+      offset: [5.0, 0.0, 0.0],
+      grid_counts: [3, 1, 3],
+      grid_spacing: [5.0, 5.0, 5.0],
+      pivot: [0.0, 0.0, 0.0],
+      step_degrees: 30.0,
+      face_outward: true,
+    }
+  }
+
+  // Position and orientation of every copy, the original object is not included
+  pub fn copies(&self, position: Vector3<f32>, orientation: Quaternion<f32>) -> Vec<(Vector3<f32>, Quaternion<f32>)> {
+    let mut copies = Vec::new();
+
+    match self.mode {
+      ARRAY_LINEAR => {
+        let offset = Vector3::new(self.offset[0], self.offset[1], self.offset[2]);
+        for i in 1..=self.count.max(0) {
+          copies.push((position + offset*i as f32, orientation));
+        }
+      },
+      ARRAY_GRID => {
+        for x in 0..self.grid_counts[0].max(1) {
+          for y in 0..self.grid_counts[1].max(1) {
+            for z in 0..self.grid_counts[2].max(1) {
+              if x == 0 && y == 0 && z == 0 {
+                continue;
+              }
+
+              let offset = Vector3::new(x as f32*self.grid_spacing[0],
+                                        y as f32*self.grid_spacing[1],
+                                        z as f32*self.grid_spacing[2]);
+              copies.push((position + offset, orientation));
+            }
+          }
+        }
+      },
+      ARRAY_RADIAL => {
+        let pivot = Vector3::new(self.pivot[0], self.pivot[1], self.pivot[2]);
+        for i in 1..=self.count.max(0) {
+          let angle = self.step_degrees*i as f32;
+          let new_position = Matrix3::from_angle_y(Deg(angle)) * (position - pivot) + pivot;
+          let mut new_orientation = orientation;
+          if self.face_outward {
+            new_orientation = facing_away_from(pivot, new_position, orientation);
+          }
+          copies.push((new_position, new_orientation));
+        }
+      },
+      _ => {},
+    }
+
+    copies.truncate(MAX_ARRAY_COPIES);
+    copies
+  }
+
+  // Returns true when the copies should be added to the scene
+  pub fn update(&mut self, ui: Option<&Ui>, window_dim: Vector2<f32>) -> bool {
+    let mut should_apply = false;
+
+    if let Some(ui) = &ui {
+      ui.window(im_str!("Array Tool"))
+        .always_auto_resize(true)
+        .position([window_dim.x*0.5, 250.0], Condition::FirstUseEver)
+        .build(|| {
+          ui.text("Mode:");
+          ui.same_line(0.0);
+          ui.push_item_width(150.0);
+          ui.combo(im_str!("##array_mode"), &mut self.mode, &[im_str!("Linear"), im_str!("Grid"), im_str!("Radial")], -1);
+          ui.push_item_width(0.0);
+
+          match self.mode {
+            ARRAY_LINEAR => {
+              ui.input_int(im_str!("Copies"), &mut self.count).build();
+              ui.input_float3(im_str!("Offset"), &mut self.offset).build();
+            },
+            ARRAY_GRID => {
+              ui.input_int3(im_str!("Counts (x, y, z)"), &mut self.grid_counts).build();
+              ui.input_float3(im_str!("Spacing"), &mut self.grid_spacing).build();
+            },
+            ARRAY_RADIAL => {
+              ui.input_int(im_str!("Copies"), &mut self.count).build();
+              ui.input_float3(im_str!("Pivot"), &mut self.pivot).build();
+              ui.input_float(im_str!("Step (deg)"), &mut self.step_degrees).build();
+              ui.checkbox(im_str!("Face outward"), &mut self.face_outward);
+            },
+            _ => {},
+          }
+
+          should_apply = ui.button(im_str!("Apply"), [0.0, 0.0]);
+        });
+    }
+
+    should_apply
+  }
+}
@@ -0,0 +1,100 @@
+use maat_graphics::imgui::*;
+
+use crate::cgmath::{Vector2, Vector3, InnerSpace};
+
+#[derive(Clone)]
+pub struct CameraBookmark {
+  pub name: String,
+  pub position: Vector3<f32>,
+  pub pitch: f32,
+  pub yaw: f32,
+  // Point the camera orbits around and how far away it is
+  pub target: Vector3<f32>,
+  pub distance: f32,
+}
+
+pub enum BookmarkAction {
+  Add(String),
+  Go(usize),
+  Remove(usize),
+}
+
+impl CameraBookmark {
+  pub fn new(name: String, position: Vector3<f32>, pitch: f32, yaw: f32, target: Vector3<f32>) -> CameraBookmark {
+    CameraBookmark {
+      name,
+      position,
+      pitch,
+      yaw,
+      target,
+      distance: (target - position).magnitude(),
+    }
+  }
+}
+
+// Pitch and yaw in degrees of a normalised camera front, matching PerspectiveCamera
+pub fn pitch_yaw_from_front(front: Vector3<f32>) -> (f32, f32) {
+  let pitch = front.y.max(-1.0).min(1.0).asin().to_degrees();
+  let mut yaw = front.z.atan2(front.x).to_degrees();
+  if yaw < 0.0 {
+    yaw += 360.0;
+  }
+
+  (pitch, yaw)
+}
+
+pub struct BookmarkWindow {
+  new_name: ImString,
+}
+
+impl BookmarkWindow {
+  pub fn new() -> BookmarkWindow {
+    BookmarkWindow {
+      new_name: ImString::with_capacity(32),
+    }
+  }
+
+  // shortcuts holds the key bound to each of the first bookmarks, empty when unbound
+  pub fn update(&mut self, ui: Option<&Ui>, bookmarks: &Vec<CameraBookmark>, shortcuts: &Vec<String>, window_dim: Vector2<f32>) -> Option<BookmarkAction> {
+    let mut action = None;
+
+    if let Some(ui) = &ui {
+      ui.window(im_str!("Camera Bookmarks"))
+        .always_auto_resize(true)
+        .position([220.0, window_dim.y - 250.0], Condition::FirstUseEver)
+        .build(|| {
+          for i in 0..bookmarks.len() {
+            if i < shortcuts.len() && shortcuts[i].len() > 0 {
+              ui.text(im_str!("{}: {}", shortcuts[i], bookmarks[i].name));
+            } else {
+              ui.text(im_str!("{}", bookmarks[i].name));
+            }
+            ui.same_line(0.0);
+            if ui.button(&im_str!("Go##{}", i), [0.0, 0.0]) {
+              action = Some(BookmarkAction::Go(i));
+            }
+            ui.same_line(0.0);
+            if ui.button(&im_str!("Remove##bookmark{}", i), [0.0, 0.0]) {
+              action = Some(BookmarkAction::Remove(i));
+            }
+          }
+
+          ui.separator();
+          ui.push_item_width(150.0);
+          ui.input_text(im_str!("##bookmark_name"), &mut self.new_name).build();
+          ui.push_item_width(0.0);
+          ui.same_line(0.0);
+          if ui.button(im_str!("Add Bookmark"), [0.0, 0.0]) {
+            let mut name = self.new_name.to_str().to_string();
+            if name.len() == 0 {
+              name = "Bookmark ".to_owned() + &(bookmarks.len()+1).to_string();
+            }
+            action = Some(BookmarkAction::Add(name));
+            self.new_name.clear();
+          }
+        });
+    }
+
+    action
+  }
+}
@@ -0,0 +1,129 @@
+use maat_graphics::imgui::*;
+
+use rand::Rng;
+
+use crate::cgmath::{Vector2, Vector3};
+
+use std::f32::consts::PI;
+
+// Samples tried per frame while dragging, keeps painting smooth instead of instant
+const ATTEMPTS_PER_FRAME: u32 = 4;
+// Density is counted per this many square units
+const DENSITY_AREA: f32 = 100.0;
+
+pub struct BrushStamp {
+  pub position: Vector3<f32>,
+  pub rotation: Vector3<f32>,
+  pub scale: f32,
+}
+
+#[derive(Clone)]
+pub struct ScatterBrush {
+  pub enabled: bool,
+  pub erase: bool,
+  pub erase_all_models: bool,
+  pub radius: f32,
+  pub density: f32,
+  pub min_spacing: f32,
+  pub yaw_range: [f32; 2],
+  pub tilt: f32,
+  pub scale_range: [f32; 2],
+}
+
+pub fn distance_xz(a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+  ((a.x-b.x)*(a.x-b.x) + (a.z-b.z)*(a.z-b.z)).sqrt()
+}
+
+fn random_between<R: Rng>(rng: &mut R, min: f32, max: f32) -> f32 {
+  if max > min {
+    rng.gen_range(min, max)
+  } else {
+    min
+  }
+}
+
+impl ScatterBrush {
+  pub fn new() -> ScatterBrush {
+    ScatterBrush {
+      enabled: false,
+      erase: false,
+      erase_all_models: false,
+      radius: 10.0,
+      density: 5.0,
+      min_spacing: 2.0,
+      yaw_range: [0.0, 360.0],
+      tilt: 0.0,
+      scale_range: [1.0, 1.0],
+    }
+  }
+
+  pub fn in_radius(&self, centre: Vector3<f32>, point: Vector3<f32>) -> bool {
+    distance_xz(centre, point) <= self.radius
+  }
+
+  // How many objects the brush area should hold when it is fully painted
+  pub fn target_count(&self) -> usize {
+    (self.density * PI * self.radius * self.radius / DENSITY_AREA).ceil() as usize
+  }
+
+  // existing holds the positions of objects of the painted model near the brush
+  pub fn scatter<R: Rng>(&self, rng: &mut R, centre: Vector3<f32>, existing: &Vec<Vector3<f32>>) -> Vec<BrushStamp> {
+    let mut count = existing.iter().filter(|position| self.in_radius(centre, **position)).count();
+    let mut occupied = existing.clone();
+    let mut stamps = Vec::new();
+
+    for _ in 0..ATTEMPTS_PER_FRAME {
+      if count >= self.target_count() {
+        break;
+      }
+
+      let angle = random_between(rng, 0.0, 2.0*PI);
+      let distance = self.radius*rng.gen::<f32>().sqrt();
+      let position = Vector3::new(centre.x + distance*angle.cos(), centre.y, centre.z + distance*angle.sin());
+
+      if occupied.iter().any(|other| distance_xz(*other, position) < self.min_spacing) {
+        continue;
+      }
+
+      let rotation = Vector3::new(random_between(rng, -self.tilt, self.tilt),
+                                  random_between(rng, self.yaw_range[0], self.yaw_range[1]),
+                                  random_between(rng, -self.tilt, self.tilt));
+      let scale = random_between(rng, self.scale_range[0], self.scale_range[1]);
+
+      occupied.push(position);
+      count += 1;
+      stamps.push(BrushStamp {
+        position,
+        rotation,
+        scale,
+      });
+    }
+
+    stamps
+  }
+
+  pub fn update(&mut self, ui: Option<&Ui>, window_dim: Vector2<f32>) {
+    if let Some(ui) = &ui {
+      ui.window(im_str!("Scatter Brush"))
+        .always_auto_resize(true)
+        .position([window_dim.x*0.5, 32.0], Condition::FirstUseEver)
+        .build(|| {
+          ui.checkbox(im_str!("Brush Active"), &mut self.enabled);
+          ui.same_line(0.0);
+          ui.checkbox(im_str!("Erase"), &mut self.erase);
+          if self.erase {
+            ui.checkbox(im_str!("Erase all models"), &mut self.erase_all_models);
+          }
+          ui.separator();
+          ui.push_item_width(200.0);
+          ui.slider_float(im_str!("Radius"), &mut self.radius, 1.0, 100.0).build();
+          ui.slider_float(im_str!("Density (per 10x10)"), &mut self.density, 0.1, 50.0).build();
+          ui.slider_float(im_str!("Min Spacing"), &mut self.min_spacing, 0.0, 20.0).build();
+          ui.input_float2(im_str!("Yaw Range (deg)"), &mut self.yaw_range).build();
+          ui.slider_float(im_str!("Max Tilt (deg)"), &mut self.tilt, 0.0, 45.0).build();
+          ui.input_float2(im_str!("Scale Range"), &mut self.scale_range).build();
+          ui.push_item_width(0.0);
+        });
+    }
+  }
+}
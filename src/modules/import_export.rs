@@ -3,23 +3,30 @@ use csv;
 use crate::modules::WorldObject;
 use crate::modules::scenes::GameOptions;
 use crate::modules::Logs;
+use crate::modules::bookmarks::CameraBookmark;
+use crate::modules::scene_script::{SceneVariable, VariableValue};
+use crate::modules::scripting::ScriptParam;
+use crate::modules::scene_browser;
+use crate::modules::scene_browser::project_file;
 
 use crate::cgmath::Vector3;
 
 use std::fs::File;
 use std::fs;
+use std::path::Path;
 
 pub fn get_models(logs: &mut Logs) -> Vec<(String, String, bool)> {
-  if let Err(e) = fs::create_dir_all("./Models") {
+  if let Err(e) = fs::create_dir_all(project_file("Models")) {
     logs.add_error(e.to_string());
   }
   
-  let paths = fs::read_dir("./Models/").unwrap();
+  let paths = fs::read_dir(project_file("Models/")).unwrap();
   
   let mut models = Vec::new();
   
+  // Locations are kept relative to the project so scenes can be moved
   for path in paths {
-    models.push(path.unwrap().path().display().to_string());
+    models.push("./Models/".to_owned() + &path.unwrap().file_name().to_string_lossy());
   }
   
   let mut known_models = Vec::new();
@@ -54,14 +61,100 @@ pub fn get_models(logs: &mut Logs) -> Vec<(String, String, bool)> {
   known_models
 }
 
+// Lua files in Scripts and its sub folders, as paths from the project folder
+pub fn get_scripts(logs: &mut Logs) -> Vec<String> {
+  let root = scene_browser::project_root();
+  if let Err(e) = fs::create_dir_all(root.join("Scripts")) {
+    logs.add_error(e.to_string());
+  }
+  
+  let mut scripts = Vec::new();
+  let mut folders = vec!(root.join("Scripts"));
+  
+  while let Some(folder) = folders.pop() {
+    match fs::read_dir(&folder) {
+      Ok(paths) => {
+        for path in paths {
+          if let Ok(entry) = path {
+            let path = entry.path();
+            if path.is_dir() {
+              folders.push(path);
+            } else if path.extension().map(|extension| extension == "lua").unwrap_or(false) {
+              let script = path.strip_prefix(&root).unwrap_or(&path).to_path_buf();
+              scripts.push(script.to_string_lossy().replace("\\", "/"));
+            }
+          }
+        }
+      },
+      Err(e) => {
+        logs.add_error("Scripts: ".to_owned() + &e.to_string());
+      }
+    }
+  }
+  
+  scripts.sort();
+  scripts
+}
+
+// Names of the scene folders in the projects Scenes folder
+pub fn get_scenes(logs: &mut Logs) -> Vec<String> {
+  if let Err(e) = fs::create_dir_all(project_file("Scenes")) {
+    logs.add_error(e.to_string());
+  }
+  
+  let mut scenes = Vec::new();
+  
+  match fs::read_dir(project_file("Scenes/")) {
+    Ok(paths) => {
+      for path in paths {
+        if let Ok(entry) = path {
+          if entry.path().is_dir() {
+            scenes.push(entry.file_name().to_string_lossy().to_string());
+          }
+        }
+      }
+    },
+    Err(e) => {
+      logs.add_error("Scenes: ".to_owned() + &e.to_string());
+    }
+  }
+  
+  scenes.sort();
+  scenes
+}
+
+// Object count and the models whose files are gone, None if the scene file can not be read
+pub fn scene_contents(project: &Path, scene_name: &str) -> Option<(usize, Vec<String>)> {
+  let file = File::open(project.join("Scenes").join(scene_name).join(scene_name.to_owned() + ".csv")).ok()?;
+  let mut reader = csv::Reader::from_reader(file);
+  
+  let mut object_count = 0;
+  let mut missing_models = Vec::new();
+  
+  for whole_object in reader.records() {
+    if let Ok(object) = whole_object {
+      object_count += 1;
+      
+      if object.len() > 3 {
+        let model = object[2].to_string();
+        if !missing_models.contains(&model) && !project.join(&object[3]).exists() {
+          missing_models.push(model);
+        }
+      }
+    }
+  }
+  
+  Some((object_count, missing_models))
+}
+
 pub fn export(scene_name: String, world_objects: &Vec<WorldObject>, camera_details: &GameOptions, logs: &mut Logs) {
-  if let Err(e) = fs::create_dir_all("./Scenes/".to_owned() + &scene_name) {
+  if let Err(e) = fs::create_dir_all(project_file("Scenes/") + &scene_name) {
     logs.add_error(e.to_string());
   }
   
-  match csv::Writer::from_path("./Scenes/".to_owned() + &scene_name + "/" + &scene_name + ".csv") {
+  match csv::Writer::from_path(project_file("Scenes/") + &scene_name + "/" + &scene_name + ".csv") {
     Ok(mut file) => {
-      file.write_record(&["id", "name", "model", "location", "instanced", "x", "y", "z", "rot_x", "rot_y", "rot_z", "size_x", "size_y", "size_z"]).unwrap();
+      file.write_record(&["id", "name", "model", "location", "instanced", "x", "y", "z", "rot_x", "rot_y", "rot_z", "size_x", "size_y", "size_z", "script"]).unwrap();
       for object in world_objects {
         let id = object.id().to_string();
         let name = object.name().to_string();
@@ -77,7 +170,8 @@ pub fn export(scene_name: String, world_objects: &Vec<WorldObject>, camera_detai
         let size_x = object.size().x.to_string();
         let size_y = object.size().y.to_string();
         let size_z = object.size().z.to_string();
-        if let Err(e) = file.write_record(&[id, name, model, location, instanced, x, y, z, rot_x, rot_y, rot_z, size_x, size_y, size_z]) {
+        let script = object.assigned_script().unwrap_or("".to_string());
+        if let Err(e) = file.write_record(&[id, name, model, location, instanced, x, y, z, rot_x, rot_y, rot_z, size_x, size_y, size_z, script]) {
           logs.add_error(e.to_string());
         }
       }
@@ -89,7 +183,7 @@ pub fn export(scene_name: String, world_objects: &Vec<WorldObject>, camera_detai
     }
   }
   
-  match csv::Writer::from_path("./Scenes/".to_owned() + &scene_name + "/camera.csv") {
+  match csv::Writer::from_path(project_file("Scenes/") + &scene_name + "/camera.csv") {
     Ok(mut file) => {
       file.write_record(&["type", "target_id", "distance", "x", "y", "z"]).unwrap();
       
@@ -107,14 +201,146 @@ pub fn export(scene_name: String, world_objects: &Vec<WorldObject>, camera_detai
       logs.add_error(e.to_string());
     }
   }
+  
+  match csv::Writer::from_path(project_file("Scenes/") + &scene_name + "/params.csv") {
+    Ok(mut file) => {
+      file.write_record(&["id", "name", "type", "value"]).unwrap();
+      for object in world_objects {
+        for param in object.script_params() {
+          let id = object.id().to_string();
+          let name = param.name.to_string();
+          let type_name = param.value.type_name().to_string();
+          let value = param.value.to_string();
+          if let Err(e) = file.write_record(&[id, name, type_name, value]) {
+            logs.add_error(e.to_string());
+          }
+        }
+      }
+      
+      file.flush().unwrap();
+    },
+    Err(e) => {
+      logs.add_error(e.to_string());
+    }
+  }
 }
 
-pub fn import(scene_name: String, logs: &mut Logs) -> (Vec<(String, String)>, Vec<WorldObject>, GameOptions) {
+pub fn export_bookmarks(scene_name: String, bookmarks: &Vec<CameraBookmark>, logs: &mut Logs) {
+  match csv::Writer::from_path(project_file("Scenes/") + &scene_name + "/bookmarks.csv") {
+    Ok(mut file) => {
+      file.write_record(&["name", "x", "y", "z", "pitch", "yaw", "target_x", "target_y", "target_z"]).unwrap();
+      for bookmark in bookmarks {
+        let name = bookmark.name.to_string();
+        let x = bookmark.position.x.to_string();
+        let y = bookmark.position.y.to_string();
+        let z = bookmark.position.z.to_string();
+        let pitch = bookmark.pitch.to_string();
+        let yaw = bookmark.yaw.to_string();
+        let target_x = bookmark.target.x.to_string();
+        let target_y = bookmark.target.y.to_string();
+        let target_z = bookmark.target.z.to_string();
+        if let Err(e) = file.write_record(&[name, x, y, z, pitch, yaw, target_x, target_y, target_z]) {
+          logs.add_error(e.to_string());
+        }
+      }
+      
+      file.flush().unwrap();
+    },
+    Err(e) => {
+      logs.add_error(e.to_string());
+    }
+  }
+}
+
+// Scenes saved before bookmarks existed have no file, that is not an error
+pub fn import_bookmarks(scene_name: String, logs: &mut Logs) -> Vec<CameraBookmark> {
+  let mut bookmarks = Vec::new();
+  
+  if let Ok(file) = File::open(project_file("Scenes/") + &scene_name + "/bookmarks.csv") {
+    let mut reader = csv::Reader::from_reader(file);
+    
+    for whole_bookmark in reader.records() {
+      match whole_bookmark {
+        Ok(bookmark) => {
+          let name: String = bookmark[0].to_string();
+          let x: f32 = bookmark[1].parse().unwrap_or(0.0);
+          let y: f32 = bookmark[2].parse().unwrap_or(0.0);
+          let z: f32 = bookmark[3].parse().unwrap_or(0.0);
+          let pitch: f32 = bookmark[4].parse().unwrap_or(0.0);
+          let yaw: f32 = bookmark[5].parse().unwrap_or(0.0);
+          let target_x: f32 = bookmark[6].parse().unwrap_or(0.0);
+          let target_y: f32 = bookmark[7].parse().unwrap_or(0.0);
+          let target_z: f32 = bookmark[8].parse().unwrap_or(0.0);
+          let position = Vector3::new(x, y, z);
+          let target = Vector3::new(target_x, target_y, target_z);
+          bookmarks.push(CameraBookmark::new(name, position, pitch, yaw, target));
+        },
+        Err(e) => {
+          logs.add_error("Bookmarks: ".to_owned() + &e.to_string());
+        }
+      }
+    }
+  }
+  
+  bookmarks
+}
+
+pub fn export_variables(scene_name: String, variables: &Vec<SceneVariable>, logs: &mut Logs) {
+  match csv::Writer::from_path(project_file("Scenes/") + &scene_name + "/variables.csv") {
+    Ok(mut file) => {
+      file.write_record(&["name", "type", "value"]).unwrap();
+      for variable in variables {
+        let name = variable.name.to_string();
+        let type_name = variable.value.type_name().to_string();
+        let value = variable.value.to_string();
+        if let Err(e) = file.write_record(&[name, type_name, value]) {
+          logs.add_error(e.to_string());
+        }
+      }
+      
+      file.flush().unwrap();
+    },
+    Err(e) => {
+      logs.add_error(e.to_string());
+    }
+  }
+}
+
+// Scenes without variables may have no file, that is not an error
+pub fn import_variables(scene_name: String, logs: &mut Logs) -> Vec<SceneVariable> {
+  let mut variables = Vec::new();
+  
+  if let Ok(file) = File::open(project_file("Scenes/") + &scene_name + "/variables.csv") {
+    let mut reader = csv::Reader::from_reader(file);
+    
+    for whole_variable in reader.records() {
+      match whole_variable {
+        Ok(variable) => {
+          let name: String = variable[0].to_string();
+          match VariableValue::parse(&variable[1], &variable[2]) {
+            Some(value) => variables.push(SceneVariable::new(name, value)),
+            None => logs.add_error("Variables: ".to_owned() + &name + " has an invalid " + &variable[1] + " value " + &variable[2]),
+          }
+        },
+        Err(e) => {
+          logs.add_error("Variables: ".to_owned() + &e.to_string());
+        }
+      }
+    }
+  }
+  
+  variables
+}
+
+// Models the scene uses, its objects and its game options
+pub type ImportedScene = (Vec<(String, String)>, Vec<WorldObject>, GameOptions);
+
+pub fn import(scene_name: String, logs: &mut Logs) -> ImportedScene {
   let mut world_objects = Vec::new();
   let mut used_models: Vec<(String, String)> = Vec::new();
   let mut game_options = GameOptions::new();
   
-  match File::open("./Scenes/".to_owned() + &scene_name + "/" + &scene_name + ".csv") {
+  match File::open(project_file("Scenes/") + &scene_name + "/" + &scene_name + ".csv") {
     Ok(file) => {
       let mut reader = csv::Reader::from_reader(file);
       
@@ -135,6 +361,8 @@ pub fn import(scene_name: String, logs: &mut Logs) -> (Vec<(String, String)>, Ve
             let size_x: f32 = object[11].parse().unwrap();
             let size_y: f32 = object[12].parse().unwrap();
             let size_z: f32 = object[13].parse().unwrap();
+            // Empty or missing in older scenes when the object uses the script named after it
+            let script = object.get(14).unwrap_or("").to_string();
             
             let mut unique = true;
             for i in 0..used_models.len() {
@@ -148,11 +376,15 @@ pub fn import(scene_name: String, logs: &mut Logs) -> (Vec<(String, String)>, Ve
               used_models.push((model.to_string(), location.to_string()));
             }
             
-            world_objects.push(WorldObject::new_with_data(id, name, scene_name.to_string(), model, location,
+            let mut world_object = WorldObject::new_with_data(id, name, scene_name.to_string(), model, location,
                                                 Vector3::new(x, y, z),
                                                 Vector3::new(rot_x, rot_y, rot_z),
                                                 Vector3::new(size_x, size_y, size_z),
-                                                instanced));
+                                                instanced);
+            if script.len() > 0 {
+              world_object.assign_script(Some(script));
+            }
+            world_objects.push(world_object);
           },
           Err(e) => {
             logs.add_error("Scene details data:".to_owned() + &e.to_string());
@@ -165,7 +397,33 @@ pub fn import(scene_name: String, logs: &mut Logs) -> (Vec<(String, String)>, Ve
     },
   }
   
-  match File::open("./Scenes/".to_owned() + &scene_name + "/camera.csv") {
+  // Scenes saved before script params existed have no file
+  if let Ok(file) = File::open(project_file("Scenes/") + &scene_name + "/params.csv") {
+    let mut reader = csv::Reader::from_reader(file);
+    
+    for whole_param in reader.records() {
+      match whole_param {
+        Ok(param) => {
+          let id: u32 = param[0].parse().unwrap_or(0);
+          let name: String = param[1].to_string();
+          match (world_objects.iter_mut().find(|object| object.id() == id), VariableValue::parse(&param[2], &param[3])) {
+            (Some(object), Some(value)) => {
+              object.add_script_param(ScriptParam::new(name, value));
+            },
+            (_, None) => {
+              logs.add_error("Params: ".to_owned() + &name + " has an invalid " + &param[2] + " value " + &param[3]);
+            },
+            (None, _) => {},
+          }
+        },
+        Err(e) => {
+          logs.add_error("Params: ".to_owned() + &e.to_string());
+        }
+      }
+    }
+  }
+  
+  match File::open(project_file("Scenes/") + &scene_name + "/camera.csv") {
     Ok(file) => {
       let mut reader = csv::Reader::from_reader(file);
       
@@ -0,0 +1,401 @@
+use maat_graphics::imgui::*;
+use maat_graphics::winit::VirtualKeyCode;
+
+use crate::modules::Logs;
+use crate::modules::scenes::SceneData;
+
+use crate::cgmath::Vector2;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+pub const KEYMAP_FILE: &str = "keymap.ini";
+
+// Every editor command that can be bound to a key
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+  NewScene,
+  SaveScene,
+  LoadScene,
+  ToggleMousePlacement,
+  ToggleShowAxis,
+  ToggleSnapToGrid,
+  ToggleRun,
+  PlaceSelectedModel,
+  FrameSelection,
+  MovePositiveX,
+  MoveNegativeX,
+  MoveUp,
+  MoveDown,
+  MovePositiveZ,
+  MoveNegativeZ,
+  FlyForward,
+  FlyBackward,
+  FlyLeft,
+  FlyRight,
+  FlyUp,
+  FlyDown,
+  GoToBookmark(usize),
+}
+
+pub const BOOKMARK_SHORTCUTS: usize = 9;
+
+pub const ACTIONS: [Action; 30] = [
+  Action::NewScene, Action::SaveScene, Action::LoadScene,
+  Action::ToggleMousePlacement, Action::ToggleShowAxis, Action::ToggleSnapToGrid, Action::ToggleRun,
+  Action::PlaceSelectedModel, Action::FrameSelection,
+  Action::MovePositiveX, Action::MoveNegativeX, Action::MoveUp, Action::MoveDown, Action::MovePositiveZ, Action::MoveNegativeZ,
+  Action::FlyForward, Action::FlyBackward, Action::FlyLeft, Action::FlyRight, Action::FlyUp, Action::FlyDown,
+  Action::GoToBookmark(0), Action::GoToBookmark(1), Action::GoToBookmark(2),
+  Action::GoToBookmark(3), Action::GoToBookmark(4), Action::GoToBookmark(5),
+  Action::GoToBookmark(6), Action::GoToBookmark(7), Action::GoToBookmark(8),
+];
+
+// Names used in the keymap file
+const KEY_NAMES: [(&str, VirtualKeyCode); 81] = [
+  ("A", VirtualKeyCode::A), ("B", VirtualKeyCode::B), ("C", VirtualKeyCode::C), ("D", VirtualKeyCode::D),
+  ("E", VirtualKeyCode::E), ("F", VirtualKeyCode::F), ("G", VirtualKeyCode::G), ("H", VirtualKeyCode::H),
+  ("I", VirtualKeyCode::I), ("J", VirtualKeyCode::J), ("K", VirtualKeyCode::K), ("L", VirtualKeyCode::L),
+  ("M", VirtualKeyCode::M), ("N", VirtualKeyCode::N), ("O", VirtualKeyCode::O), ("P", VirtualKeyCode::P),
+  ("Q", VirtualKeyCode::Q), ("R", VirtualKeyCode::R), ("S", VirtualKeyCode::S), ("T", VirtualKeyCode::T),
+  ("U", VirtualKeyCode::U), ("V", VirtualKeyCode::V), ("W", VirtualKeyCode::W), ("X", VirtualKeyCode::X),
+  ("Y", VirtualKeyCode::Y), ("Z", VirtualKeyCode::Z),
+  ("0", VirtualKeyCode::Key0), ("1", VirtualKeyCode::Key1), ("2", VirtualKeyCode::Key2), ("3", VirtualKeyCode::Key3),
+  ("4", VirtualKeyCode::Key4), ("5", VirtualKeyCode::Key5), ("6", VirtualKeyCode::Key6), ("7", VirtualKeyCode::Key7),
+  ("8", VirtualKeyCode::Key8), ("9", VirtualKeyCode::Key9),
+  ("F1", VirtualKeyCode::F1), ("F2", VirtualKeyCode::F2), ("F3", VirtualKeyCode::F3), ("F4", VirtualKeyCode::F4),
+  ("F5", VirtualKeyCode::F5), ("F6", VirtualKeyCode::F6), ("F7", VirtualKeyCode::F7), ("F8", VirtualKeyCode::F8),
+  ("F9", VirtualKeyCode::F9), ("F10", VirtualKeyCode::F10), ("F11", VirtualKeyCode::F11), ("F12", VirtualKeyCode::F12),
+  ("Numpad0", VirtualKeyCode::Numpad0), ("Numpad1", VirtualKeyCode::Numpad1), ("Numpad2", VirtualKeyCode::Numpad2),
+  ("Numpad3", VirtualKeyCode::Numpad3), ("Numpad4", VirtualKeyCode::Numpad4), ("Numpad5", VirtualKeyCode::Numpad5),
+  ("Numpad6", VirtualKeyCode::Numpad6), ("Numpad7", VirtualKeyCode::Numpad7), ("Numpad8", VirtualKeyCode::Numpad8),
+  ("Numpad9", VirtualKeyCode::Numpad9),
+  ("Escape", VirtualKeyCode::Escape), ("Space", VirtualKeyCode::Space), ("Tab", VirtualKeyCode::Tab),
+  ("Enter", VirtualKeyCode::Return), ("Backspace", VirtualKeyCode::Back), ("Delete", VirtualKeyCode::Delete),
+  ("Insert", VirtualKeyCode::Insert), ("Home", VirtualKeyCode::Home), ("End", VirtualKeyCode::End),
+  ("PageUp", VirtualKeyCode::PageUp), ("PageDown", VirtualKeyCode::PageDown),
+  ("Left", VirtualKeyCode::Left), ("Right", VirtualKeyCode::Right), ("Up", VirtualKeyCode::Up), ("Down", VirtualKeyCode::Down),
+  ("Minus", VirtualKeyCode::Minus), ("Equals", VirtualKeyCode::Equals), ("Comma", VirtualKeyCode::Comma),
+  ("Period", VirtualKeyCode::Period), ("Slash", VirtualKeyCode::Slash), ("Semicolon", VirtualKeyCode::Semicolon),
+  ("Apostrophe", VirtualKeyCode::Apostrophe), ("Grave", VirtualKeyCode::Grave),
+];
+
+pub fn key_name(key: VirtualKeyCode) -> Option<&'static str> {
+  KEY_NAMES.iter().find(|(_, code)| *code == key).map(|(name, _)| *name)
+}
+
+pub fn key_names() -> Vec<&'static str> {
+  KEY_NAMES.iter().map(|(name, _)| *name).collect()
+}
+
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+  KEY_NAMES.iter().find(|(key_name, _)| key_name.eq_ignore_ascii_case(name)).map(|(_, code)| *code)
+}
+
+impl Action {
+  // Key in the keymap file
+  pub fn name(&self) -> String {
+    match self {
+      Action::NewScene => "new_scene".to_string(),
+      Action::SaveScene => "save_scene".to_string(),
+      Action::LoadScene => "load_scene".to_string(),
+      Action::ToggleMousePlacement => "toggle_mouse_placement".to_string(),
+      Action::ToggleShowAxis => "toggle_show_axis".to_string(),
+      Action::ToggleSnapToGrid => "toggle_snap_to_grid".to_string(),
+      Action::ToggleRun => "toggle_run".to_string(),
+      Action::PlaceSelectedModel => "place_selected_model".to_string(),
+      Action::FrameSelection => "frame_selection".to_string(),
+      Action::MovePositiveX => "move_positive_x".to_string(),
+      Action::MoveNegativeX => "move_negative_x".to_string(),
+      Action::MoveUp => "move_up".to_string(),
+      Action::MoveDown => "move_down".to_string(),
+      Action::MovePositiveZ => "move_positive_z".to_string(),
+      Action::MoveNegativeZ => "move_negative_z".to_string(),
+      Action::FlyForward => "fly_forward".to_string(),
+      Action::FlyBackward => "fly_backward".to_string(),
+      Action::FlyLeft => "fly_left".to_string(),
+      Action::FlyRight => "fly_right".to_string(),
+      Action::FlyUp => "fly_up".to_string(),
+      Action::FlyDown => "fly_down".to_string(),
+      Action::GoToBookmark(idx) => "go_to_bookmark_".to_owned() + &(idx+1).to_string(),
+    }
+  }
+
+  pub fn description(&self) -> String {
+    match self {
+      Action::NewScene => "New scene".to_string(),
+      Action::SaveScene => "Save scene".to_string(),
+      Action::LoadScene => "Load scene".to_string(),
+      Action::ToggleMousePlacement => "Toggle mouse placement".to_string(),
+      Action::ToggleShowAxis => "Toggle axis".to_string(),
+      Action::ToggleSnapToGrid => "Toggle snap to grid".to_string(),
+      Action::ToggleRun => "Run or stop the game".to_string(),
+      Action::PlaceSelectedModel => "Place selected model".to_string(),
+      Action::FrameSelection => "Frame selection".to_string(),
+      Action::MovePositiveX => "Move object +X".to_string(),
+      Action::MoveNegativeX => "Move object -X".to_string(),
+      Action::MoveUp => "Move object or placing height up".to_string(),
+      Action::MoveDown => "Move object or placing height down".to_string(),
+      Action::MovePositiveZ => "Move object +Z".to_string(),
+      Action::MoveNegativeZ => "Move object -Z".to_string(),
+      Action::FlyForward => "Fly forward".to_string(),
+      Action::FlyBackward => "Fly backward".to_string(),
+      Action::FlyLeft => "Fly left".to_string(),
+      Action::FlyRight => "Fly right".to_string(),
+      Action::FlyUp => "Fly up".to_string(),
+      Action::FlyDown => "Fly down".to_string(),
+      Action::GoToBookmark(idx) => "Go to camera bookmark ".to_owned() + &(idx+1).to_string(),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyChord {
+  pub key: VirtualKeyCode,
+  pub ctrl: bool,
+  pub shift: bool,
+  pub alt: bool,
+}
+
+impl KeyChord {
+  pub fn new(key: VirtualKeyCode) -> KeyChord {
+    KeyChord {
+      key,
+      ctrl: false,
+      shift: false,
+      alt: false,
+    }
+  }
+
+  pub fn ctrl(key: VirtualKeyCode) -> KeyChord {
+    KeyChord {
+      ctrl: true,
+      .. KeyChord::new(key)
+    }
+  }
+
+  // Written like Ctrl+Shift+M
+  pub fn parse(text: &str) -> Option<KeyChord> {
+    let mut parts = text.split('+').map(|part| part.trim()).collect::<Vec<&str>>();
+    let key = key_from_name(parts.pop()?)?;
+
+    let mut chord = KeyChord::new(key);
+    for modifier in parts {
+      match modifier.to_lowercase().as_str() {
+        "ctrl" => chord.ctrl = true,
+        "shift" => chord.shift = true,
+        "alt" => chord.alt = true,
+        _ => return None,
+      }
+    }
+
+    Some(chord)
+  }
+
+  pub fn name(&self) -> String {
+    let mut name = "".to_string();
+    if self.ctrl {
+      name.push_str("Ctrl+");
+    }
+    if self.shift {
+      name.push_str("Shift+");
+    }
+    if self.alt {
+      name.push_str("Alt+");
+    }
+
+    name + key_name(self.key).unwrap_or("?")
+  }
+}
+
+pub struct Keymap {
+  bindings: Vec<(Action, Option<KeyChord>)>,
+}
+
+impl Keymap {
+  pub fn new() -> Keymap {
+    let mut bindings = vec!(
+      (Action::NewScene, Some(KeyChord::ctrl(VirtualKeyCode::N))),
+      (Action::SaveScene, Some(KeyChord::ctrl(VirtualKeyCode::S))),
+      (Action::LoadScene, Some(KeyChord::ctrl(VirtualKeyCode::O))),
+      (Action::ToggleMousePlacement, Some(KeyChord::ctrl(VirtualKeyCode::M))),
+      (Action::ToggleShowAxis, Some(KeyChord::ctrl(VirtualKeyCode::A))),
+      (Action::ToggleSnapToGrid, Some(KeyChord::ctrl(VirtualKeyCode::G))),
+      (Action::ToggleRun, Some(KeyChord::new(VirtualKeyCode::F6))),
+      (Action::PlaceSelectedModel, Some(KeyChord::new(VirtualKeyCode::Key1))),
+      (Action::FrameSelection, Some(KeyChord::new(VirtualKeyCode::F))),
+      (Action::MovePositiveX, Some(KeyChord::new(VirtualKeyCode::U))),
+      (Action::MoveNegativeX, Some(KeyChord::new(VirtualKeyCode::J))),
+      (Action::MoveUp, Some(KeyChord::new(VirtualKeyCode::I))),
+      (Action::MoveDown, Some(KeyChord::new(VirtualKeyCode::K))),
+      (Action::MovePositiveZ, Some(KeyChord::new(VirtualKeyCode::O))),
+      (Action::MoveNegativeZ, Some(KeyChord::new(VirtualKeyCode::L))),
+      (Action::FlyForward, Some(KeyChord::new(VirtualKeyCode::W))),
+      (Action::FlyBackward, Some(KeyChord::new(VirtualKeyCode::S))),
+      (Action::FlyLeft, Some(KeyChord::new(VirtualKeyCode::A))),
+      (Action::FlyRight, Some(KeyChord::new(VirtualKeyCode::D))),
+      (Action::FlyUp, Some(KeyChord::new(VirtualKeyCode::E))),
+      (Action::FlyDown, Some(KeyChord::new(VirtualKeyCode::Q))),
+    );
+
+    let digits = [VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+                  VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6,
+                  VirtualKeyCode::Key7, VirtualKeyCode::Key8, VirtualKeyCode::Key9];
+    for i in 0..BOOKMARK_SHORTCUTS {
+      bindings.push((Action::GoToBookmark(i), Some(KeyChord::ctrl(digits[i]))));
+    }
+
+    Keymap {
+      bindings,
+    }
+  }
+
+  // Missing file gets the default bindings written out so there is something to edit
+  pub fn load(location: &Path, logs: &mut Logs) -> Keymap {
+    let mut keymap = Keymap::new();
+
+    match File::open(location) {
+      Ok(f) => {
+        for line in BufReader::new(f).lines() {
+          match line {
+            Ok(line) => {
+              let line = line.trim();
+              if line.len() == 0 || line.starts_with('#') {
+                continue;
+              }
+
+              match line.find('=') {
+                Some(idx) => {
+                  let (name, chord) = line.split_at(idx);
+                  keymap.set_binding(name.trim(), chord[1..].trim(), logs);
+                },
+                None => {
+                  logs.add_error("Keymap: no key for ".to_owned() + line);
+                }
+              }
+            },
+            Err(e) => {
+              logs.add_error("Keymap: ".to_owned() + &e.to_string());
+            }
+          }
+        }
+      },
+      Err(_) => {
+        keymap.save(location, logs);
+      }
+    }
+
+    keymap
+  }
+
+  pub fn save(&self, location: &Path, logs: &mut Logs) {
+    match File::create(location) {
+      Ok(f) => {
+        let mut f = BufWriter::new(f);
+        let mut lines = vec!("# action = key, for example Ctrl+Shift+S. Leave the key empty to unbind".to_string());
+        for (action, chord) in &self.bindings {
+          lines.push(action.name() + " = " + &chord.map_or("".to_string(), |chord| chord.name()));
+        }
+
+        for line in lines {
+          if let Err(e) = f.write_all((line + "\n").as_bytes()) {
+            logs.add_error("Keymap: ".to_owned() + &e.to_string());
+            return;
+          }
+        }
+      },
+      Err(e) => {
+        logs.add_error("Keymap: ".to_owned() + &e.to_string());
+      }
+    }
+  }
+
+  fn set_binding(&mut self, name: &str, chord: &str, logs: &mut Logs) {
+    let new_chord = {
+      if chord.len() == 0 {
+        None
+      } else {
+        match KeyChord::parse(chord) {
+          Some(chord) => Some(chord),
+          None => {
+            logs.add_error("Keymap: unknown key ".to_owned() + chord + " for " + name);
+            return;
+          }
+        }
+      }
+    };
+
+    match self.bindings.iter_mut().find(|(action, _)| action.name() == name) {
+      Some(binding) => binding.1 = new_chord,
+      None => logs.add_error("Keymap: unknown action ".to_owned() + name),
+    }
+  }
+
+  pub fn chord(&self, action: Action) -> Option<KeyChord> {
+    self.bindings.iter().find(|(bound, _)| *bound == action).and_then(|(_, chord)| *chord)
+  }
+
+  // Text for menus, empty when unbound
+  pub fn shortcut(&self, action: Action) -> String {
+    self.chord(action).map_or("".to_string(), |chord| chord.name())
+  }
+
+  // Pressed this frame with exactly the bound modifiers
+  pub fn triggered(&self, action: Action, data: &SceneData) -> bool {
+    match self.chord(action) {
+      Some(chord) => {
+        let modifiers = data.modifiers;
+        data.key_pressed(chord.key) &&
+        chord.ctrl == modifiers.ctrl && chord.shift == modifiers.shift && chord.alt == modifiers.alt
+      },
+      None => false,
+    }
+  }
+
+  // Down this frame with at least the bound modifiers
+  pub fn held(&self, action: Action, data: &SceneData) -> bool {
+    match self.chord(action) {
+      Some(chord) => {
+        let modifiers = data.modifiers;
+        data.key_held(chord.key) &&
+        (!chord.ctrl || modifiers.ctrl) && (!chord.shift || modifiers.shift) && (!chord.alt || modifiers.alt)
+      },
+      None => false,
+    }
+  }
+
+  // Returns true if the keymap file should be read again
+  pub fn update(&self, ui: Option<&Ui>, location: &Path, window_dim: Vector2<f32>) -> bool {
+    let mut should_reload = false;
+
+    if let Some(ui) = &ui {
+      ui.window(im_str!("Keyboard Shortcuts"))
+        .size([400.0, 500.0], Condition::FirstUseEver)
+        .position([window_dim.x*0.5 - 200.0, 55.0], Condition::FirstUseEver)
+        .build(|| {
+          ui.text_wrapped(&ImString::new("Edit ".to_owned() + &location.display().to_string() + " to change bindings"));
+          if ui.button(im_str!("Reload"), [0.0, 0.0]) {
+            should_reload = true;
+          }
+          ui.separator();
+
+          ui.columns(2, im_str!("shortcuts"), true);
+          for action in ACTIONS.iter() {
+            ui.text(im_str!("{}", action.description()));
+            ui.next_column();
+            let shortcut = self.shortcut(*action);
+            if shortcut.len() == 0 {
+              ui.text("Unbound");
+            } else {
+              ui.text(im_str!("{}", shortcut));
+            }
+            ui.next_column();
+          }
+          ui.columns(1, im_str!(""), false);
+        });
+    }
+
+    should_reload
+  }
+}
@@ -47,8 +47,8 @@ impl LightObject {
      if let Some(ui) = &ui {
         ui.window(im_str!("Light Options"))
             .always_auto_resize(true)
-            .size([200.0, 200.0], Condition::Appearing)
-            .position([window_dim.x - 500.0, 200.0], Condition::Appearing)
+            .size([200.0, 200.0], Condition::FirstUseEver)
+            .position([window_dim.x - 500.0, 200.0], Condition::FirstUseEver)
             .build(|| {
               ui.new_line();
               ui.text(im_str!("Position"));
@@ -26,16 +26,25 @@ impl Logs {
     }
   }
   
+  pub fn set_window_size(&mut self, window_size: Vector2<f32>) {
+    self.position = window_size*0.5;
+  }
+  
   pub fn is_shown(&self) -> bool {
     self.show
   }
   
   pub fn add_error(&mut self, err: String) {
     self.last_error = err.to_string();
+    self.write_error(err);
+    self.show = true;
+  }
+  
+  // Only goes in log.ini, for errors that are shown somewhere else
+  pub fn write_error(&mut self, err: String) {
     if let Err(_) = self.error_log.write(&(err.to_owned() + "\n").as_bytes()) {
       println!("Writting logs failed");
     }
-    self.show = true;
   }
   
   pub fn draw(&mut self, ui: Option<&Ui>) {
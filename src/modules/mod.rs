@@ -4,6 +4,19 @@ pub use self::logs::Logs;
 
 pub mod scenes;
 pub mod import_export;
+pub mod placement;
+pub mod brush;
+pub mod array_tool;
+pub mod align;
+pub mod rotation;
+pub mod bookmarks;
+pub mod preferences;
+pub mod scene_browser;
+pub mod keymap;
+pub mod scripting;
+pub mod script_errors;
+pub mod scene_script;
+pub mod script_templates;
 
 mod logs;
 mod world_object;
@@ -0,0 +1,257 @@
+use crate::cgmath::{Vector3, InnerSpace};
+
+// Rays closer to parallel than this never reach the plane in a usable spot
+const PARALLEL_EPSILON: f32 = 0.00001;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PlacementPlane {
+  // XZ plane at the placing height
+  Ground,
+  // Upright plane through the selection, turned towards the camera
+  Vertical,
+  // Plane through the selection, perpendicular to the camera front
+  CameraFacing,
+}
+
+#[derive(Clone, Copy)]
+pub struct Plane {
+  pub point: Vector3<f32>,
+  pub normal: Vector3<f32>,
+}
+
+impl Plane {
+  pub fn new(point: Vector3<f32>, normal: Vector3<f32>) -> Plane {
+    Plane {
+      point,
+      normal: normal.normalize(),
+    }
+  }
+
+  pub fn horizontal(height: f32) -> Plane {
+    Plane::new(Vector3::new(0.0, height, 0.0), Vector3::new(0.0, 1.0, 0.0))
+  }
+}
+
+impl PlacementPlane {
+  pub fn name(&self) -> &'static str {
+    match self {
+      PlacementPlane::Ground => "Ground (XZ)",
+      PlacementPlane::Vertical => "Vertical",
+      PlacementPlane::CameraFacing => "Camera Facing",
+    }
+  }
+
+  // anchor is the position of the current selection
+  pub fn to_plane(&self, height: f32, anchor: Vector3<f32>, camera_front: Vector3<f32>) -> Plane {
+    match self {
+      PlacementPlane::Ground => {
+        Plane::horizontal(height)
+      },
+      PlacementPlane::Vertical => {
+        let normal = Vector3::new(camera_front.x, 0.0, camera_front.z);
+        if normal.magnitude2() < PARALLEL_EPSILON {
+          // Looking straight down, there is no sensible upright plane
+          Plane::horizontal(height)
+        } else {
+          Plane::new(anchor, normal)
+        }
+      },
+      PlacementPlane::CameraFacing => {
+        Plane::new(anchor, camera_front)
+      },
+    }
+  }
+}
+
+// Distance along the ray to the plane, None if it is parallel or points away
+pub fn ray_plane_distance(origin: Vector3<f32>, direction: Vector3<f32>, plane: &Plane) -> Option<f32> {
+  let denominator = plane.normal.dot(direction);
+  if denominator.abs() < PARALLEL_EPSILON {
+    return None;
+  }
+
+  let t = (plane.point - origin).dot(plane.normal) / denominator;
+  if t < 0.0 {
+    return None;
+  }
+
+  Some(t)
+}
+
+pub fn ray_plane_intersection(origin: Vector3<f32>, direction: Vector3<f32>, plane: &Plane) -> Option<Vector3<f32>> {
+  ray_plane_distance(origin, direction, plane).map(|t| origin + direction*t)
+}
+
+// Axis aligned box, rotations of the object are not taken into account
+#[derive(Clone, Copy)]
+pub struct BoundingBox {
+  pub min: Vector3<f32>,
+  pub max: Vector3<f32>,
+}
+
+impl BoundingBox {
+  pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> BoundingBox {
+    BoundingBox {
+      min,
+      max,
+    }
+  }
+
+  // Models are centred on their position, extent is the full width, height and depth
+  pub fn from_centre(centre: Vector3<f32>, extent: Vector3<f32>) -> BoundingBox {
+    let half = extent*0.5;
+    BoundingBox::new(centre - half, centre + half)
+  }
+
+  pub fn centre(&self) -> Vector3<f32> {
+    (self.min + self.max)*0.5
+  }
+}
+
+// Distance along the ray to the first hit on the box and the normal of the face it
+// went through, None if it is missed. A ray starting inside the box hits at its
+// origin with the normal pointing back along the ray.
+pub fn ray_box_intersection(origin: Vector3<f32>, direction: Vector3<f32>, bounds: &BoundingBox) -> Option<(f32, Vector3<f32>)> {
+  let mut t_min = 0.0f32;
+  let mut t_max = std::f32::MAX;
+  let mut entry_axis = None;
+
+  for axis in 0..3 {
+    if direction[axis].abs() < PARALLEL_EPSILON {
+      if origin[axis] < bounds.min[axis] || origin[axis] > bounds.max[axis] {
+        return None;
+      }
+      continue;
+    }
+
+    let inverse = 1.0 / direction[axis];
+    let mut t1 = (bounds.min[axis] - origin[axis]) * inverse;
+    let mut t2 = (bounds.max[axis] - origin[axis]) * inverse;
+    if t1 > t2 {
+      std::mem::swap(&mut t1, &mut t2);
+    }
+
+    if t1 > t_min {
+      t_min = t1;
+      entry_axis = Some(axis);
+    }
+    t_max = t_max.min(t2);
+    if t_min > t_max {
+      return None;
+    }
+  }
+
+  let normal = match entry_axis {
+    Some(axis) => {
+      let mut normal = Vector3::new(0.0, 0.0, 0.0);
+      normal[axis] = -direction[axis].signum();
+      normal
+    },
+    None => -direction.normalize(),
+  };
+
+  Some((t_min, normal))
+}
+
+// Half the extent of the box measured along an axis aligned face normal
+pub fn half_extent_along(extent: Vector3<f32>, normal: Vector3<f32>) -> f32 {
+  (extent.x*normal.x.abs() + extent.y*normal.y.abs() + extent.z*normal.z.abs())*0.5
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EPSILON: f32 = 0.0001;
+
+  fn unit_box() -> BoundingBox {
+    BoundingBox::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0))
+  }
+
+  fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+    (a - b).magnitude() < EPSILON
+  }
+
+  #[test]
+  fn plane_hit_below_origin() {
+    let plane = Plane::horizontal(0.0);
+    let hit = ray_plane_intersection(Vector3::new(2.0, 5.0, 3.0), Vector3::new(0.0, -1.0, 0.0), &plane);
+    assert!(close(hit.unwrap(), Vector3::new(2.0, 0.0, 3.0)));
+  }
+
+  #[test]
+  fn plane_hit_at_an_angle() {
+    let plane = Plane::horizontal(1.0);
+    let direction = Vector3::new(1.0, -1.0, 0.0).normalize();
+    let distance = ray_plane_distance(Vector3::new(0.0, 3.0, 0.0), direction, &plane).unwrap();
+    assert!((distance - 2.0*2.0f32.sqrt()).abs() < EPSILON);
+    let hit = ray_plane_intersection(Vector3::new(0.0, 3.0, 0.0), direction, &plane).unwrap();
+    assert!(close(hit, Vector3::new(2.0, 1.0, 0.0)));
+  }
+
+  #[test]
+  fn plane_parallel_ray_misses() {
+    let plane = Plane::horizontal(0.0);
+    assert!(ray_plane_intersection(Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), &plane).is_none());
+    assert!(ray_plane_intersection(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), &plane).is_none());
+  }
+
+  #[test]
+  fn plane_behind_origin_misses() {
+    let plane = Plane::horizontal(0.0);
+    assert!(ray_plane_intersection(Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 1.0, 0.0), &plane).is_none());
+    assert!(ray_plane_intersection(Vector3::new(0.0, -1.0, 0.0), Vector3::new(0.3, -1.0, 0.0), &plane).is_none());
+  }
+
+  #[test]
+  fn box_hit_on_each_face() {
+    let bounds = unit_box();
+    let faces = [
+      Vector3::new(1.0, 0.0, 0.0),
+      Vector3::new(-1.0, 0.0, 0.0),
+      Vector3::new(0.0, 1.0, 0.0),
+      Vector3::new(0.0, -1.0, 0.0),
+      Vector3::new(0.0, 0.0, 1.0),
+      Vector3::new(0.0, 0.0, -1.0),
+    ];
+
+    for &face in faces.iter() {
+      let origin = face*5.0;
+      let (t, normal) = ray_box_intersection(origin, -face, &bounds).unwrap();
+      assert!((t - 4.0).abs() < EPSILON);
+      assert!(close(origin - face*t, face));
+      assert!(close(normal, face));
+    }
+  }
+
+  #[test]
+  fn box_side_hit_reports_side_normal() {
+    let bounds = unit_box();
+    let direction = Vector3::new(1.0, -0.2, 0.0).normalize();
+    let (t, normal) = ray_box_intersection(Vector3::new(-5.0, 0.5, 0.0), direction, &bounds).unwrap();
+    assert!(close(normal, Vector3::new(-1.0, 0.0, 0.0)));
+    assert!(((Vector3::new(-5.0, 0.5, 0.0) + direction*t).x + 1.0).abs() < EPSILON);
+    assert!((half_extent_along(Vector3::new(2.0, 4.0, 6.0), normal) - 1.0).abs() < EPSILON);
+  }
+
+  #[test]
+  fn box_parallel_ray_outside_slab_misses() {
+    let bounds = unit_box();
+    assert!(ray_box_intersection(Vector3::new(-5.0, 2.0, 0.0), Vector3::new(1.0, 0.0, 0.0), &bounds).is_none());
+    assert!(ray_box_intersection(Vector3::new(-5.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), &bounds).is_none());
+  }
+
+  #[test]
+  fn box_behind_origin_misses() {
+    let bounds = unit_box();
+    assert!(ray_box_intersection(Vector3::new(5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), &bounds).is_none());
+  }
+
+  #[test]
+  fn box_ray_starting_inside_hits_at_origin() {
+    let bounds = unit_box();
+    let (t, normal) = ray_box_intersection(Vector3::new(0.2, -0.3, 0.5), Vector3::new(0.0, 1.0, 0.0), &bounds).unwrap();
+    assert!(t.abs() < EPSILON);
+    assert!(close(normal, Vector3::new(0.0, -1.0, 0.0)));
+  }
+}
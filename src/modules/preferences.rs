@@ -0,0 +1,234 @@
+use crate::modules::Logs;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+const PREFERENCES_FILE: &str = "preferences.ini";
+// Window positions and sizes, written by imgui itself
+const LAYOUT_FILE: &str = "layout.ini";
+const MAX_RECENT: usize = 10;
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 3.0;
+
+#[derive(Clone, PartialEq)]
+pub struct RecentScene {
+  pub project: String,
+  pub scene: String,
+}
+
+pub struct Preferences {
+  // Folder the editor was started from, its settings stay here whatever project is open
+  editor_dir: PathBuf,
+  pub recent_scenes: Vec<RecentScene>,
+  pub recent_projects: Vec<String>,
+  // Opened straight after loading instead of the welcome screen
+  pub default_scene: Option<RecentScene>,
+  pub snap_to_grid: bool,
+  pub show_axis: bool,
+  pub place_with_mouse: bool,
+  pub surface_snap: bool,
+  pub fly_camera: bool,
+  pub pause_on_script_error: bool,
+  pub euler_order: i32,
+  pub camera_speed: f32,
+  pub ui_scale: f32,
+  // None until the editor has been closed once, the editor defaults are used
+  pub open_windows: Option<Vec<String>>,
+}
+
+// Absolute so the layout is found whatever the working directory
+pub fn layout_location() -> String {
+  match env::current_dir() {
+    Ok(dir) => dir.join(LAYOUT_FILE).display().to_string(),
+    Err(_) => "./".to_owned() + LAYOUT_FILE,
+  }
+}
+
+fn parse_bool(value: &str, default: bool) -> bool {
+  match value {
+    "true" => true,
+    "false" => false,
+    _ => default,
+  }
+}
+
+impl Preferences {
+  pub fn new() -> Preferences {
+    let editor_dir = env::current_dir().unwrap_or(PathBuf::from("."));
+
+    Preferences {
+      editor_dir,
+      recent_scenes: Vec::new(),
+      recent_projects: Vec::new(),
+      default_scene: None,
+      snap_to_grid: false,
+      show_axis: true,
+      place_with_mouse: true,
+      surface_snap: false,
+      fly_camera: false,
+      pause_on_script_error: false,
+      euler_order: 0,
+      camera_speed: 50.0,
+      ui_scale: 1.0,
+      open_windows: None,
+    }
+  }
+
+  // A missing file just means the editor has not been closed yet, defaults are used
+  pub fn load(logs: &mut Logs) -> Preferences {
+    let mut preferences = Preferences::new();
+
+    if let Ok(f) = File::open(preferences.editor_file(PREFERENCES_FILE)) {
+      for line in BufReader::new(f).lines() {
+        match line {
+          Ok(line) => {
+            let line = line.trim();
+            if line.len() == 0 || line.starts_with('#') {
+              continue;
+            }
+
+            match line.find('=') {
+              Some(idx) => {
+                let (key, value) = line.split_at(idx);
+                preferences.set_value(key.trim(), value[1..].trim());
+              },
+              None => {
+                logs.add_error("Preferences: no value for ".to_owned() + line);
+              }
+            }
+          },
+          Err(e) => {
+            logs.add_error("Preferences: ".to_owned() + &e.to_string());
+          }
+        }
+      }
+    }
+
+    preferences
+  }
+
+  pub fn save(&self, logs: &mut Logs) {
+    match File::create(self.editor_file(PREFERENCES_FILE)) {
+      Ok(f) => {
+        let mut f = BufWriter::new(f);
+        for (key, value) in self.values() {
+          if let Err(e) = f.write_all((key + "=" + &value + "\n").as_bytes()) {
+            logs.add_error("Preferences: ".to_owned() + &e.to_string());
+            return;
+          }
+        }
+      },
+      Err(e) => {
+        logs.add_error("Preferences: ".to_owned() + &e.to_string());
+      }
+    }
+  }
+
+  // Location of a settings file that belongs to the editor rather than a project
+  pub fn editor_file(&self, file_name: &str) -> PathBuf {
+    self.editor_dir.join(file_name)
+  }
+
+  pub fn last_scene(&self) -> Option<&RecentScene> {
+    self.recent_scenes.first()
+  }
+
+  // Moves the scene to the top of the recent list
+  pub fn add_recent_scene(&mut self, project: String, scene: String) {
+    let recent = RecentScene { project: project.to_string(), scene };
+    self.recent_scenes.retain(|other| *other != recent);
+    self.recent_scenes.insert(0, recent);
+    self.recent_scenes.truncate(MAX_RECENT);
+
+    self.add_recent_project(project);
+  }
+
+  pub fn add_recent_project(&mut self, project: String) {
+    self.recent_projects.retain(|other| *other != project);
+    self.recent_projects.insert(0, project);
+    self.recent_projects.truncate(MAX_RECENT);
+  }
+
+  pub fn set_ui_scale(&mut self, ui_scale: f32) {
+    self.ui_scale = ui_scale.max(MIN_UI_SCALE).min(MAX_UI_SCALE);
+  }
+
+  // Scene names never contain '|', project paths might
+  fn parse_scene(value: &str) -> Option<RecentScene> {
+    value.rfind('|').map(|idx| {
+      let (project, scene) = value.split_at(idx);
+      RecentScene { project: project.to_string(), scene: scene[1..].to_string() }
+    })
+  }
+
+  fn set_value(&mut self, key: &str, value: &str) {
+    match key {
+      "recent_scene" => {
+        if let Some(recent) = Preferences::parse_scene(value) {
+          if self.recent_scenes.len() < MAX_RECENT {
+            self.recent_scenes.push(recent);
+          }
+        }
+      },
+      "default_scene" => {
+        self.default_scene = Preferences::parse_scene(value);
+      },
+      "snap_to_grid" => self.snap_to_grid = parse_bool(value, self.snap_to_grid),
+      "show_axis" => self.show_axis = parse_bool(value, self.show_axis),
+      "place_with_mouse" => self.place_with_mouse = parse_bool(value, self.place_with_mouse),
+      "surface_snap" => self.surface_snap = parse_bool(value, self.surface_snap),
+      "fly_camera" => self.fly_camera = parse_bool(value, self.fly_camera),
+      "pause_on_script_error" => self.pause_on_script_error = parse_bool(value, self.pause_on_script_error),
+      "euler_order" => self.euler_order = value.parse().unwrap_or(self.euler_order),
+      "camera_speed" => self.camera_speed = value.parse().unwrap_or(self.camera_speed),
+      "ui_scale" => {
+        let ui_scale = value.parse().unwrap_or(self.ui_scale);
+        self.set_ui_scale(ui_scale);
+      },
+      "open_windows" => {
+        self.open_windows = Some(value.split(',').map(|name| name.trim().to_string())
+                                      .filter(|name| name.len() > 0).collect());
+      },
+      "recent_project" => {
+        if value.len() > 0 && self.recent_projects.len() < MAX_RECENT {
+          self.recent_projects.push(value.to_string());
+        }
+      },
+      _ => {},
+    }
+  }
+
+  fn values(&self) -> Vec<(String, String)> {
+    let mut values = Vec::new();
+
+    for recent in &self.recent_scenes {
+      values.push(("recent_scene".to_string(), recent.project.to_owned() + "|" + &recent.scene));
+    }
+
+    for project in &self.recent_projects {
+      values.push(("recent_project".to_string(), project.to_string()));
+    }
+
+    if let Some(scene) = &self.default_scene {
+      values.push(("default_scene".to_string(), scene.project.to_owned() + "|" + &scene.scene));
+    }
+
+    values.push(("snap_to_grid".to_string(), self.snap_to_grid.to_string()));
+    values.push(("show_axis".to_string(), self.show_axis.to_string()));
+    values.push(("place_with_mouse".to_string(), self.place_with_mouse.to_string()));
+    values.push(("surface_snap".to_string(), self.surface_snap.to_string()));
+    values.push(("fly_camera".to_string(), self.fly_camera.to_string()));
+    values.push(("pause_on_script_error".to_string(), self.pause_on_script_error.to_string()));
+    values.push(("euler_order".to_string(), self.euler_order.to_string()));
+    values.push(("camera_speed".to_string(), self.camera_speed.to_string()));
+    values.push(("ui_scale".to_string(), self.ui_scale.to_string()));
+
+    if let Some(windows) = &self.open_windows {
+      values.push(("open_windows".to_string(), windows.join(",")));
+    }
+
+    values
+  }
+}
@@ -0,0 +1,235 @@
+use crate::cgmath::{Vector3, Quaternion, Matrix3, Rotation3, Deg, Rad};
+
+// All Euler angles in the editor are in degrees. An order of XYZ means the
+// rotation around X is applied first, then Y, then Z, all around the world
+// axes. The scene csv, scripts and DrawCall::draw_model use RENDER_ORDER,
+// the model's rotation is Rz*Ry*Rx.
+pub const RENDER_ORDER: EulerOrder = EulerOrder::XYZ;
+
+// Below this cos(middle angle) the first and last axes line up
+const GIMBAL_EPSILON: f32 = 0.000001;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EulerOrder {
+  XYZ,
+  XZY,
+  YXZ,
+  YZX,
+  ZXY,
+  ZYX,
+}
+
+pub const EULER_ORDERS: [EulerOrder; 6] = [EulerOrder::XYZ, EulerOrder::XZY, EulerOrder::YXZ,
+                                           EulerOrder::YZX, EulerOrder::ZXY, EulerOrder::ZYX];
+
+impl EulerOrder {
+  pub fn from_index(idx: i32) -> EulerOrder {
+    EULER_ORDERS[(idx.max(0) as usize).min(EULER_ORDERS.len()-1)]
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      EulerOrder::XYZ => "XYZ",
+      EulerOrder::XZY => "XZY",
+      EulerOrder::YXZ => "YXZ",
+      EulerOrder::YZX => "YZX",
+      EulerOrder::ZXY => "ZXY",
+      EulerOrder::ZYX => "ZYX",
+    }
+  }
+
+  // Axes in the order they are applied
+  fn axes(&self) -> (usize, usize, usize) {
+    match self {
+      EulerOrder::XYZ => (0, 1, 2),
+      EulerOrder::XZY => (0, 2, 1),
+      EulerOrder::YXZ => (1, 0, 2),
+      EulerOrder::YZX => (1, 2, 0),
+      EulerOrder::ZXY => (2, 0, 1),
+      EulerOrder::ZYX => (2, 1, 0),
+    }
+  }
+
+  // Cyclic orders are even permutations of XYZ
+  fn parity(&self) -> f32 {
+    match self {
+      EulerOrder::XYZ | EulerOrder::YZX | EulerOrder::ZXY => 1.0,
+      _ => -1.0,
+    }
+  }
+}
+
+fn axis_rotation(axis: usize, degrees: f32) -> Quaternion<f32> {
+  match axis {
+    0 => Quaternion::from_angle_x(Deg(degrees)),
+    1 => Quaternion::from_angle_y(Deg(degrees)),
+    _ => Quaternion::from_angle_z(Deg(degrees)),
+  }
+}
+
+// cgmath matrices are column major
+fn element(matrix: &Matrix3<f32>, row: usize, column: usize) -> f32 {
+  matrix[column][row]
+}
+
+pub fn quaternion_from_euler(degrees: Vector3<f32>, order: EulerOrder) -> Quaternion<f32> {
+  let (a, b, c) = order.axes();
+  axis_rotation(c, degrees[c]) * axis_rotation(b, degrees[b]) * axis_rotation(a, degrees[a])
+}
+
+pub fn euler_from_quaternion(orientation: Quaternion<f32>, order: EulerOrder) -> Vector3<f32> {
+  let (a, b, c) = order.axes();
+  let sign = order.parity();
+  let matrix = Matrix3::from(orientation);
+
+  let mut angles = Vector3::new(0.0, 0.0, 0.0);
+  let sin_middle = (-sign*element(&matrix, c, a)).max(-1.0).min(1.0);
+  angles[b] = sin_middle.asin();
+
+  if (1.0 - sin_middle.abs()) > GIMBAL_EPSILON {
+    angles[a] = (sign*element(&matrix, c, b)).atan2(element(&matrix, c, c));
+    angles[c] = (sign*element(&matrix, b, a)).atan2(element(&matrix, a, a));
+  } else {
+    // Gimbal lock, put the whole turn into the first axis
+    angles[a] = (-sign*element(&matrix, b, c)).atan2(element(&matrix, b, b));
+    angles[c] = 0.0;
+  }
+
+  Vector3::new(Deg::from(Rad(angles.x)).0, Deg::from(Rad(angles.y)).0, Deg::from(Rad(angles.z)).0)
+}
+
+// Euler angles as they were typed in, converting back from the orientation can give a
+// different but equivalent set of angles
+#[derive(Clone, Copy)]
+pub struct EulerCache {
+  order: EulerOrder,
+  degrees: Vector3<f32>,
+  orientation: Quaternion<f32>,
+}
+
+impl EulerCache {
+  pub fn new(order: EulerOrder, degrees: Vector3<f32>, orientation: Quaternion<f32>) -> EulerCache {
+    EulerCache {
+      order,
+      degrees,
+      orientation,
+    }
+  }
+
+  // None once the orientation has been changed by something else
+  pub fn get(&self, orientation: Quaternion<f32>, order: EulerOrder) -> Option<Vector3<f32>> {
+    if self.order == order && self.orientation == orientation {
+      Some(self.degrees)
+    } else {
+      None
+    }
+  }
+}
+
+pub fn cached_euler(cache: &Option<EulerCache>, orientation: Quaternion<f32>, order: EulerOrder) -> Vector3<f32> {
+  cache.and_then(|cache| cache.get(orientation, order)).unwrap_or_else(|| euler_from_quaternion(orientation, order))
+}
+
+// Reflecting across the plane perpendicular to axis flips the turns around the other two axes
+pub fn mirror_orientation(orientation: Quaternion<f32>, axis: usize) -> Quaternion<f32> {
+  let mut mirrored = orientation;
+  for other in 0..3 {
+    if other != axis {
+      mirrored.v[other] = -orientation.v[other];
+    }
+  }
+  mirrored
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cgmath::InnerSpace;
+
+  const EPSILON: f32 = 0.001;
+
+  fn same_orientation(a: Quaternion<f32>, b: Quaternion<f32>) -> bool {
+    // q and -q are the same turn
+    (a.s*b.s + a.v.dot(b.v)).abs() > 1.0 - EPSILON
+  }
+
+  // Rotation part of the model matrix draw_model builds from its angles
+  fn render_rotation(degrees: Vector3<f32>) -> Matrix3<f32> {
+    Matrix3::from_angle_z(Deg(degrees.z))*Matrix3::from_angle_y(Deg(degrees.y))*Matrix3::from_angle_x(Deg(degrees.x))
+  }
+
+  fn with_middle(order: EulerOrder, first: f32, middle: f32, last: f32) -> Vector3<f32> {
+    let (a, b, c) = order.axes();
+    let mut degrees = Vector3::new(0.0, 0.0, 0.0);
+    degrees[a] = first;
+    degrees[b] = middle;
+    degrees[c] = last;
+    degrees
+  }
+
+  #[test]
+  fn round_trip_gives_back_the_same_angles() {
+    let angles = [(10.0, 20.0, 30.0), (-45.0, 60.0, 120.0), (170.0, -80.0, -10.0), (0.0, 0.0, 0.0), (-150.0, 45.0, 179.0)];
+    for order in EULER_ORDERS.iter() {
+      for (first, middle, last) in angles.iter() {
+        let degrees = with_middle(*order, *first, *middle, *last);
+        let result = euler_from_quaternion(quaternion_from_euler(degrees, *order), *order);
+        assert!((result - degrees).magnitude() < EPSILON, "{} {:?} came back as {:?}", order.name(), degrees, result);
+      }
+    }
+  }
+
+  #[test]
+  fn round_trip_at_gimbal_lock_keeps_the_orientation() {
+    let angles = [(30.0, 90.0, 45.0), (-120.0, -90.0, 10.0), (0.0, 90.0, 0.0), (0.0, -90.0, 75.0)];
+    for order in EULER_ORDERS.iter() {
+      for (first, middle, last) in angles.iter() {
+        let degrees = with_middle(*order, *first, *middle, *last);
+        let orientation = quaternion_from_euler(degrees, *order);
+        let result = euler_from_quaternion(orientation, *order);
+        assert!(same_orientation(orientation, quaternion_from_euler(result, *order)), "{} {:?} came back as {:?}", order.name(), degrees, result);
+      }
+    }
+  }
+
+  #[test]
+  fn render_order_matches_the_model_matrix() {
+    let angles = [(10.0, 20.0, 30.0), (-45.0, 60.0, 120.0), (170.0, -80.0, -10.0), (90.0, 0.0, -90.0)];
+    for (x, y, z) in angles.iter() {
+      let degrees = Vector3::new(*x, *y, *z);
+      let orientation = quaternion_from_euler(degrees, RENDER_ORDER);
+      let expected = render_rotation(degrees);
+      let result = Matrix3::from(orientation);
+      for column in 0..3 {
+        assert!((result[column] - expected[column]).magnitude() < EPSILON, "{:?} gave a different model matrix", degrees);
+      }
+
+      let round_trip = euler_from_quaternion(orientation, RENDER_ORDER);
+      let matrix = render_rotation(round_trip);
+      for column in 0..3 {
+        assert!((matrix[column] - expected[column]).magnitude() < EPSILON, "{:?} came back as {:?}", degrees, round_trip);
+      }
+    }
+  }
+
+  #[test]
+  fn order_applies_first_axis_first() {
+    // X then Y turns the up vector onto +X, Y then X turns it onto +Z
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let xyz = quaternion_from_euler(Vector3::new(90.0, 90.0, 0.0), EulerOrder::XYZ) * up;
+    assert!((xyz - Vector3::new(1.0, 0.0, 0.0)).magnitude() < EPSILON);
+    let yxz = quaternion_from_euler(Vector3::new(90.0, 90.0, 0.0), EulerOrder::YXZ) * up;
+    assert!((yxz - Vector3::new(0.0, 0.0, 1.0)).magnitude() < EPSILON);
+  }
+
+  #[test]
+  fn cache_only_applies_to_its_orientation_and_order() {
+    let degrees = Vector3::new(0.0, 100.0, 0.0);
+    let orientation = quaternion_from_euler(degrees, EulerOrder::XYZ);
+    let cache = Some(EulerCache::new(EulerOrder::XYZ, degrees, orientation));
+    assert_eq!(cached_euler(&cache, orientation, EulerOrder::XYZ), degrees);
+    assert!(cached_euler(&cache, orientation, EulerOrder::ZYX) != degrees);
+    let turned = quaternion_from_euler(Vector3::new(0.0, 10.0, 0.0), EulerOrder::XYZ);
+    assert!(cached_euler(&cache, turned, EulerOrder::XYZ) != degrees);
+  }
+}
@@ -0,0 +1,245 @@
+use maat_graphics::imgui::*;
+
+use crate::modules::Logs;
+use crate::modules::import_export;
+use crate::modules::preferences::Preferences;
+
+use crate::cgmath::Vector2;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+thread_local! {
+  // Folder of the open project, the working directory is left alone so files that
+  // belong to the editor are still found
+  static PROJECT_ROOT: RefCell<PathBuf> = RefCell::new(env::current_dir().unwrap_or(PathBuf::from(".")));
+}
+
+pub enum BrowserAction {
+  New(String),
+  // Project folder and scene name
+  Open(String, String),
+  OpenFolder(String),
+  Cancel,
+}
+
+pub struct SceneSummary {
+  pub project: String,
+  pub scene: String,
+  pub modified: Option<SystemTime>,
+  pub object_count: usize,
+  pub missing_models: Vec<String>,
+  pub found: bool,
+}
+
+impl SceneSummary {
+  pub fn read(project: &str, scene: &str) -> SceneSummary {
+    let project_path = Path::new(project);
+    let scene_file = project_path.join("Scenes").join(scene).join(scene.to_owned() + ".csv");
+    let modified = fs::metadata(&scene_file).and_then(|metadata| metadata.modified()).ok();
+
+    let (found, object_count, missing_models) = match import_export::scene_contents(project_path, scene) {
+      Some((object_count, missing_models)) => (true, object_count, missing_models),
+      None => (false, 0, Vec::new()),
+    };
+
+    SceneSummary {
+      project: project.to_string(),
+      scene: scene.to_string(),
+      modified,
+      object_count,
+      missing_models,
+      found,
+    }
+  }
+
+  pub fn details(&self) -> String {
+    if !self.found {
+      return "scene file not found".to_string();
+    }
+
+    let modified = match self.modified {
+      Some(modified) => time_since(modified),
+      None => "unknown".to_string(),
+    };
+
+    self.object_count.to_string() + " objects, modified " + &modified
+  }
+}
+
+pub fn project_root() -> PathBuf {
+  PROJECT_ROOT.with(|root| root.borrow().clone())
+}
+
+pub fn current_project() -> String {
+  project_root().display().to_string()
+}
+
+// Path of a file in the open project, relative is from the project folder such as
+// "Scenes/" or a model location saved in a scene
+pub fn project_file(relative: &str) -> String {
+  project_root().join(relative.trim_start_matches("./")).display().to_string()
+}
+
+// Returns false if the folder could not be opened
+pub fn open_project(project: &str, preferences: &mut Preferences, logs: &mut Logs) -> bool {
+  let root = project_root().join(project);
+  if !root.is_dir() {
+    logs.add_error("Project ".to_owned() + project + " is not a folder");
+    return false;
+  }
+
+  PROJECT_ROOT.with(|project_root| *project_root.borrow_mut() = root);
+  preferences.add_recent_project(current_project());
+  preferences.save(logs);
+  true
+}
+
+fn time_since(time: SystemTime) -> String {
+  let seconds = match SystemTime::now().duration_since(time) {
+    Ok(duration) => duration.as_secs(),
+    Err(_) => 0,
+  };
+
+  if seconds < 60 {
+    "just now".to_string()
+  } else if seconds < 60*60 {
+    (seconds/60).to_string() + " minutes ago"
+  } else if seconds < 60*60*24 {
+    (seconds/(60*60)).to_string() + " hours ago"
+  } else {
+    (seconds/(60*60*24)).to_string() + " days ago"
+  }
+}
+
+pub struct SceneBrowser {
+  new_name: ImString,
+  folder: ImString,
+  project: String,
+  project_scenes: Vec<SceneSummary>,
+  recent_scenes: Vec<SceneSummary>,
+  recent_projects: Vec<String>,
+  can_cancel: bool,
+}
+
+impl SceneBrowser {
+  pub fn new(can_cancel: bool) -> SceneBrowser {
+    SceneBrowser {
+      new_name: ImString::with_capacity(32),
+      folder: ImString::with_capacity(256),
+      project: current_project(),
+      project_scenes: Vec::new(),
+      recent_scenes: Vec::new(),
+      recent_projects: Vec::new(),
+      can_cancel,
+    }
+  }
+
+  // Reads the scene files again, done when the browser opens rather than every frame
+  pub fn refresh(&mut self, preferences: &Preferences, logs: &mut Logs) {
+    self.project = current_project();
+
+    self.project_scenes = import_export::get_scenes(logs).iter()
+                            .map(|scene| SceneSummary::read(&self.project, scene))
+                            .collect();
+
+    self.recent_scenes = preferences.recent_scenes.iter()
+                           .map(|recent| SceneSummary::read(&recent.project, &recent.scene))
+                           .collect();
+
+    self.recent_projects = preferences.recent_projects.clone();
+  }
+
+  fn scene_list(ui: &Ui, id: &str, scenes: &Vec<SceneSummary>, show_project: bool, action: &mut Option<BrowserAction>) {
+    if scenes.len() == 0 {
+      ui.text("  None");
+    }
+
+    for i in 0..scenes.len() {
+      let summary = &scenes[i];
+      if summary.found {
+        if ui.button(&im_str!("Open##{}{}", id, i), [0.0, 0.0]) {
+          *action = Some(BrowserAction::Open(summary.project.to_string(), summary.scene.to_string()));
+        }
+        ui.same_line(0.0);
+      }
+
+      ui.text(im_str!("{}  ({})", summary.scene, summary.details()));
+      if show_project {
+        ui.text(im_str!("    {}", summary.project));
+      }
+      if summary.missing_models.len() > 0 {
+        ui.text(im_str!("    Warning: missing models {}", summary.missing_models.join(", ")));
+      }
+    }
+  }
+
+  pub fn update(&mut self, ui: Option<&Ui>, window_dim: Vector2<f32>) -> Option<BrowserAction> {
+    let mut action = None;
+
+    if let Some(ui) = &ui {
+      ui.window(im_str!("Scenes"))
+        .always_auto_resize(true)
+        .collapsible(false)
+        .position([window_dim.x*0.25, window_dim.y*0.15], Condition::Appearing)
+        .build(|| {
+          ui.text(im_str!("Project: {}", self.project));
+          ui.separator();
+
+          ui.text("Recent Scenes");
+          SceneBrowser::scene_list(ui, "recent", &self.recent_scenes, true, &mut action);
+          ui.separator();
+
+          ui.text("Scenes in Project");
+          SceneBrowser::scene_list(ui, "project", &self.project_scenes, false, &mut action);
+          ui.separator();
+
+          ui.text("Recent Projects");
+          if self.recent_projects.len() == 0 {
+            ui.text("  None");
+          }
+          for i in 0..self.recent_projects.len() {
+            if ui.button(&im_str!("Open##project{}", i), [0.0, 0.0]) {
+              action = Some(BrowserAction::OpenFolder(self.recent_projects[i].to_string()));
+            }
+            ui.same_line(0.0);
+            ui.text(im_str!("{}", self.recent_projects[i]));
+          }
+          ui.separator();
+
+          ui.push_item_width(250.0);
+          ui.input_text(im_str!("##new_scene_name"), &mut self.new_name).build();
+          ui.same_line(0.0);
+          if ui.button(im_str!("New Scene"), [0.0, 0.0]) {
+            let mut name = self.new_name.to_str().trim().to_string();
+            if name.len() == 0 {
+              name = "new_scene".to_string();
+            }
+            action = Some(BrowserAction::New(name));
+          }
+
+          ui.input_text(im_str!("##project_folder"), &mut self.folder).build();
+          ui.push_item_width(0.0);
+          ui.same_line(0.0);
+          if ui.button(im_str!("Open Folder"), [0.0, 0.0]) {
+            let folder = self.folder.to_str().trim().to_string();
+            if folder.len() > 0 {
+              action = Some(BrowserAction::OpenFolder(folder));
+            }
+          }
+
+          if self.can_cancel {
+            ui.separator();
+            if ui.button(im_str!("Cancel"), [0.0, 0.0]) {
+              action = Some(BrowserAction::Cancel);
+            }
+          }
+        });
+    }
+
+    action
+  }
+}
@@ -0,0 +1,292 @@
+use maat_graphics::imgui::*;
+
+use hlua::Lua;
+
+use crate::modules::Logs;
+use crate::modules::scripting;
+use crate::modules::scripting::{Callback, ModifiedTimes};
+use crate::modules::script_errors::ScriptErrors;
+use crate::modules::scene_browser::project_file;
+
+use std::io::Write;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::time::SystemTime;
+
+use open;
+
+pub const SCENE_SCRIPT_FILE: &str = "scene.lua";
+
+pub const VARIABLE_TYPES: [&str; 3] = ["number", "bool", "string"];
+
+#[derive(Clone, PartialEq)]
+pub enum VariableValue {
+  Number(f32),
+  Bool(bool),
+  Text(String),
+}
+
+impl VariableValue {
+  // The type names stored in variables.csv
+  pub fn type_name(&self) -> &'static str {
+    VARIABLE_TYPES[self.type_index()]
+  }
+
+  fn type_index(&self) -> usize {
+    match self {
+      VariableValue::Number(_) => 0,
+      VariableValue::Bool(_) => 1,
+      VariableValue::Text(_) => 2,
+    }
+  }
+
+  pub fn parse(type_name: &str, value: &str) -> Option<VariableValue> {
+    match type_name {
+      "number" => value.parse().ok().map(|value| VariableValue::Number(value)),
+      "bool" => value.parse().ok().map(|value| VariableValue::Bool(value)),
+      "string" => Some(VariableValue::Text(value.to_string())),
+      _ => None,
+    }
+  }
+
+  // Keeps what it can of the old value when the type is changed in the editor
+  fn with_type(&self, type_index: usize) -> VariableValue {
+    let text = self.to_string();
+    match type_index {
+      0 => VariableValue::Number(text.parse().unwrap_or(0.0)),
+      1 => VariableValue::Bool(text == "true"),
+      _ => VariableValue::Text(text),
+    }
+  }
+
+  pub fn to_string(&self) -> String {
+    match self {
+      VariableValue::Number(value) => value.to_string(),
+      VariableValue::Bool(value) => value.to_string(),
+      VariableValue::Text(value) => value.to_string(),
+    }
+  }
+}
+
+// Saved with the scene, scripts see them in scene.variables while running
+#[derive(Clone)]
+pub struct SceneVariable {
+  pub name: String,
+  pub value: VariableValue,
+}
+
+impl SceneVariable {
+  pub fn new(name: String, value: VariableValue) -> SceneVariable {
+    SceneVariable {
+      name,
+      value,
+    }
+  }
+}
+
+// Widget for the value's type, also used for script params in the inspector
+pub fn edit_value(ui: &Ui, label: &ImStr, value: &mut VariableValue) {
+  match value {
+    VariableValue::Number(value) => {
+      ui.input_float(label, value).build();
+    },
+    VariableValue::Bool(value) => {
+      ui.checkbox(label, value);
+    },
+    VariableValue::Text(value) => {
+      let mut imstr_value = ImString::with_capacity(64);
+      imstr_value.push_str(value);
+      if ui.input_text(label, &mut imstr_value).build() {
+        *value = imstr_value.to_str().to_string();
+      }
+    },
+  }
+}
+
+// Table of variables drawn inside the Scene Details window
+pub fn edit_variables(ui: &Ui, variables: &mut Vec<SceneVariable>) {
+  let mut remove = None;
+
+  ui.text("Scene Variables:");
+  for i in 0..variables.len() {
+    let variable = &mut variables[i];
+
+    let mut imstr_name = ImString::with_capacity(32);
+    imstr_name.push_str(&variable.name);
+    ui.push_item_width(100.0);
+    if ui.input_text(&im_str!("##variable_name{}", i), &mut imstr_name).build() {
+      variable.name = imstr_name.to_str().to_string();
+    }
+
+    ui.same_line(0.0);
+    ui.push_item_width(70.0);
+    let mut type_index = variable.value.type_index() as i32;
+    if ui.combo(&im_str!("##variable_type{}", i), &mut type_index, &[im_str!("number"), im_str!("bool"), im_str!("string")], -1) {
+      variable.value = variable.value.with_type(type_index as usize);
+    }
+
+    ui.same_line(0.0);
+    ui.push_item_width(100.0);
+    edit_value(ui, &im_str!("##variable_value{}", i), &mut variable.value);
+    ui.push_item_width(0.0);
+
+    ui.same_line(0.0);
+    if ui.button(&im_str!("Remove##variable{}", i), [0.0, 0.0]) {
+      remove = Some(i);
+    }
+  }
+
+  if let Some(i) = remove {
+    variables.remove(i);
+  }
+
+  if ui.button(im_str!("Add Variable"), [0.0, 0.0]) {
+    let mut number = variables.len()+1;
+    while variables.iter().any(|variable| variable.name == "variable".to_owned() + &number.to_string()) {
+      number += 1;
+    }
+    variables.push(SceneVariable::new("variable".to_owned() + &number.to_string(), VariableValue::Number(0.0)));
+  }
+}
+
+// The optional scene.lua next to the scene file, it has no self but sees
+// the same scene, input and scene.variables as object scripts
+pub struct SceneScript {
+  // Scene folder the script is in, it moves when the scene is saved under a new name
+  directory: String,
+  callbacks: Vec<Callback>,
+  modified: Option<SystemTime>,
+}
+
+impl SceneScript {
+  pub fn new(directory: String) -> SceneScript {
+    SceneScript {
+      directory,
+      callbacks: Vec::new(),
+      modified: None,
+    }
+  }
+
+  fn file(&self) -> String {
+    project_file(&self.path())
+  }
+
+  // From the project folder, used as the chunk name
+  fn path(&self) -> String {
+    "Scenes/".to_owned() + &self.directory + "/" + SCENE_SCRIPT_FILE
+  }
+
+  pub fn exists(&self) -> bool {
+    Path::new(&self.file()).exists()
+  }
+
+  pub fn create(&mut self, logs: &mut Logs) {
+    if self.exists() {
+      return;
+    }
+
+    if let Err(e) = fs::create_dir_all(project_file("Scenes/") + &self.directory) {
+      logs.add_error(e.to_string());
+    }
+
+    let data = "-- Runs once for the whole scene, there is no self
+-- scene.variables holds the Scene Variables from Scene Details,
+--   object scripts see the same table
+
+-- Called once when run mode starts, before any object's init
+function init()
+end
+
+-- Called every frame before the objects update, dt is in seconds
+function update(dt)
+end
+
+-- Called 60 times a second whatever the frame rate
+function fixed_update(dt)
+end
+
+-- Called when run mode ends
+function on_destroy()
+end";
+
+    match File::create(self.file()) {
+      Ok(mut f) => {
+        if let Err(e) = f.write_all(data.as_bytes()) {
+          logs.add_error(e.to_string());
+        }
+      },
+      Err(e) => {
+        logs.add_error(e.to_string());
+      }
+    }
+  }
+
+  pub fn open(&self, logs: &mut Logs) {
+    if let Err(e) = open::that(self.file()) {
+      logs.add_error(e.to_string());
+    }
+  }
+
+  // Copies the script when the scene is saved under a different name
+  pub fn save(&mut self, directory: String, logs: &mut Logs) {
+    if directory == self.directory {
+      return;
+    }
+
+    if self.exists() {
+      let file_to = project_file("Scenes/") + &directory + "/" + SCENE_SCRIPT_FILE;
+      if let Err(e) = fs::copy(self.file(), file_to) {
+        logs.add_error(e.to_string());
+      }
+    }
+    self.directory = directory;
+  }
+
+  pub fn load(&mut self, lua: &mut Lua, errors: &mut ScriptErrors) {
+    self.callbacks.clear();
+    self.compile(lua, false, errors);
+  }
+
+  // Same as WorldObject::reload_changed_script
+  pub fn reload_changed(&mut self, lua: &mut Lua, times: &mut ModifiedTimes, errors: &mut ScriptErrors) -> bool {
+    let modified = times.get(&self.file());
+    if modified.is_none() || modified == self.modified {
+      return false;
+    }
+
+    self.compile(lua, true, errors)
+  }
+
+  fn compile(&mut self, lua: &mut Lua, reload: bool, errors: &mut ScriptErrors) -> bool {
+    self.modified = fs::metadata(self.file()).and_then(|metadata| metadata.modified()).ok();
+
+    let source = match fs::read_to_string(self.file()) {
+      Ok(source) => source,
+      Err(_) => {
+        return false;
+      }
+    };
+
+    match scripting::compile_scene(lua, &self.path(), &source, reload) {
+      Ok(callbacks) => {
+        self.callbacks = callbacks;
+        true
+      },
+      Err(e) => {
+        errors.add_scene(&self.path(), e);
+        false
+      }
+    }
+  }
+
+  pub fn call(&mut self, lua: &mut Lua, callback: Callback, delta_time: f32, errors: &mut ScriptErrors) {
+    if !self.callbacks.contains(&callback) {
+      return;
+    }
+
+    if let Some(e) = scripting::call_scene(lua, callback, delta_time) {
+      errors.add_scene(&self.path(), e);
+    }
+  }
+}
@@ -1,4 +1,5 @@
 use maat_graphics::DrawCall;
+use maat_graphics::camera;
 use maat_graphics::camera::PerspectiveCamera;
 use maat_graphics::imgui::*;
 
@@ -9,15 +10,39 @@ use crate::modules::scenes::SceneData;
 use crate::modules::WorldObject;
 use crate::modules::LightObject;
 use crate::modules::import_export;
-use crate::modules::import_export::{import, export};
+use crate::modules::import_export::{import, export, import_bookmarks, export_bookmarks, import_variables, export_variables, ImportedScene};
+use crate::modules::placement;
+use crate::modules::placement::{Plane, PlacementPlane, BoundingBox};
+use crate::modules::brush;
+use crate::modules::brush::ScatterBrush;
+use crate::modules::array_tool::ArrayTool;
+use crate::modules::align;
+use crate::modules::align::{AlignAction, AlignAnchor};
+use crate::modules::rotation;
+use crate::modules::rotation::EulerOrder;
+use crate::modules::bookmarks;
+use crate::modules::bookmarks::{CameraBookmark, BookmarkWindow, BookmarkAction};
 use crate::modules::Logs;
+use crate::modules::preferences::{Preferences, RecentScene};
+use crate::modules::scene_browser;
+use crate::modules::scene_browser::{SceneBrowser, BrowserAction, project_file};
+use crate::modules::keymap;
+use crate::modules::keymap::{Keymap, Action};
+use crate::modules::scripting;
+use crate::modules::scripting::{Callback, ModifiedTimes};
+use crate::modules::script_errors::{ScriptErrors, ScriptErrorAction};
+use crate::modules::scene_script;
+use crate::modules::scene_script::{SceneScript, SceneVariable};
+use crate::modules::script_templates;
+use crate::modules::script_templates::ScriptTemplate;
 
 use rand;
 use rand::{thread_rng};
 
-use crate::cgmath::{Vector2, Vector3};
+use crate::cgmath::{Vector2, Vector3, InnerSpace};
 
 use std::fs;
+use std::path::PathBuf;
 
 const CAMERA_DEFAULT_X: f32 = 83.93359;
 const CAMERA_DEFAULT_Y: f32 = 128.62776;
@@ -26,6 +51,16 @@ const CAMERA_DEFAULT_PITCH: f32 = -62.27426;
 const CAMERA_DEFAULT_YAW: f32 = 210.10083;
 const CAMERA_DEFAULT_SPEED: f32 = 50.0;
 
+// Distance kept from a framed selection, in multiples of its bounding radius
+const FRAME_DISTANCE_SCALE: f32 = 2.5;
+const FRAME_MIN_RADIUS: f32 = 1.0;
+
+const FLY_LOOK_SENSITIVITY: f32 = 0.2;
+const FLY_FAST_MULTIPLIER: f32 = 3.0;
+const FLY_SLOW_MULTIPLIER: f32 = 0.25;
+const FLY_MIN_SPEED: f32 = 1.0;
+const FLY_MAX_PITCH: f32 = 89.0;
+
 #[derive(Clone)]
 pub struct Light {
   pos: Vector3<f32>,
@@ -56,6 +91,12 @@ pub struct EditorWindows {
   scene_details: bool,
   camera_options: bool,
   lights: bool,
+  scatter_brush: bool,
+  array_tool: bool,
+  align_tools: bool,
+  bookmarks: bool,
+  shortcuts: bool,
+  script_errors: bool,
   load_window: bool,
   saved: bool,
   error_window: bool,
@@ -66,12 +107,23 @@ pub struct EditorOptions {
   snap_to_grid: bool,
   show_axis: bool,
   place_with_mouse: bool,
+  placement_plane: PlacementPlane,
+  surface_snap: bool,
+  align_axis: i32,
+  mirror_about_selection: bool,
+  euler_order: i32,
+  fly_camera: bool,
+  fly_speed: f32,
   instanced_option: i32,
 }
 
 #[derive(Clone)]
 pub struct GameOptions {
   first_game_loop: bool,
+  // Time not yet used up by fixed_update steps
+  fixed_timer: f32,
+  // Scripts stop being called but nothing is reset
+  paused: bool,
   pub camera_type: i32,
   pub camera_target: i32,
   pub camera_distance: f32,
@@ -89,19 +141,52 @@ impl EditorWindows {
       scene_details: true,
       camera_options: true,
       lights: true,
-      load_window: true,
+      scatter_brush: false,
+      array_tool: false,
+      align_tools: false,
+      bookmarks: false,
+      shortcuts: false,
+      script_errors: false,
+      load_window: false,
       saved: false,
       error_window: false,
     }
   }
 }
 
+impl EditorWindows {
+  // Windows whose open state is kept in the preferences file, by name
+  fn remembered(&mut self) -> Vec<(&'static str, &mut bool)> {
+    vec!(
+      ("world_objects", &mut self.world_objects),
+      ("model_list", &mut self.model_list),
+      ("loaded_models", &mut self.loaded_models),
+      ("scene_details", &mut self.scene_details),
+      ("camera_options", &mut self.camera_options),
+      ("lights", &mut self.lights),
+      ("scatter_brush", &mut self.scatter_brush),
+      ("array_tool", &mut self.array_tool),
+      ("align_tools", &mut self.align_tools),
+      ("bookmarks", &mut self.bookmarks),
+      ("shortcuts", &mut self.shortcuts),
+      ("script_errors", &mut self.script_errors),
+    )
+  }
+}
+
 impl EditorOptions {
   pub fn new() -> EditorOptions {
     EditorOptions {
       snap_to_grid: false,
       show_axis: true,
       place_with_mouse: true,
+      placement_plane: PlacementPlane::Ground,
+      surface_snap: false,
+      align_axis: 0,
+      mirror_about_selection: false,
+      euler_order: 0,
+      fly_camera: false,
+      fly_speed: CAMERA_DEFAULT_SPEED,
       instanced_option: 0,
     }
   }
@@ -111,6 +196,8 @@ impl GameOptions {
   pub fn new() -> GameOptions {
     GameOptions {
       first_game_loop: true,
+      fixed_timer: 0.0,
+      paused: false,
       camera_type: 0,
       camera_target: 0,
       camera_distance: 90.0,
@@ -125,6 +212,8 @@ pub struct EditorScreen {
   data: SceneData,
   rng: rand::prelude::ThreadRng,
   camera: PerspectiveCamera,
+  // Last point the camera was told to orbit around
+  camera_target: Vector3<f32>,
   last_mouse_pos: Vector2<f32>,
   placing_height: f32,
   object_being_placed: Option<WorldObject>,
@@ -133,23 +222,43 @@ pub struct EditorScreen {
   mouse_state: MouseState,
   selected_model: i32,
   object_selected: i32,
+  multi_selection: Vec<u32>,
   known_models: Vec<(String, String, bool)>,
+  // Shared scripts that can be assigned to objects
+  known_scripts: Vec<String>,
+  script_templates: Vec<ScriptTemplate>,
   run_game: bool,
-  f6_released_last_frame: bool,
   right_clicked_last_frame: bool,
   update_mouse_cursor: bool,
   scene_name: String,
-  load_scene_option: i32,
   logs: Logs,
   windows: EditorWindows,
   options: EditorOptions,
   game_options: GameOptions,
   instanced_buffers: Vec<String>,
   instanced_buffers_added: Vec<String>,
+  // Deleted while running, on_destroy is called once lua is available
+  destroyed_objects: Vec<WorldObject>,
+  script_errors: ScriptErrors,
+  // Scene changes made by scripts, undone when run mode ends
+  spawned_objects: Vec<u32>,
+  removed_objects: Vec<WorldObject>,
+  scene_variables: Vec<SceneVariable>,
+  scene_script: SceneScript,
+  // Time since script files were last checked for changes
+  script_check_timer: f32,
+  preferences: Preferences,
+  scene_browser: SceneBrowser,
+  brush: ScatterBrush,
+  array_tool: ArrayTool,
+  bookmarks: Vec<CameraBookmark>,
+  bookmark_window: BookmarkWindow,
+  keymap: Keymap,
+  keymap_location: PathBuf,
 }
 
 impl EditorScreen {
-  pub fn new(window_size: Vector2<f32>, model_sizes: Vec<(String, Vector3<f32>)>) -> EditorScreen {
+  pub fn new(window_size: Vector2<f32>, model_sizes: Vec<(String, Vector3<f32>)>, logs: Logs, preferences: Preferences) -> EditorScreen {
     let rng =  thread_rng();
     
     let mut camera = PerspectiveCamera::default_vk();
@@ -158,12 +267,17 @@ impl EditorScreen {
     camera.set_yaw(CAMERA_DEFAULT_YAW);
     camera.set_move_speed(CAMERA_DEFAULT_SPEED);
     
-    let mut logs = Logs::new(window_size);
+    let mut logs = logs;
+    logs.set_window_size(window_size);
     
-    EditorScreen {
+    let keymap_location = preferences.editor_file(keymap::KEYMAP_FILE);
+    let keymap = Keymap::load(&keymap_location, &mut logs);
+    
+    let mut editor = EditorScreen {
       data: SceneData::new(window_size, model_sizes),
       rng,
       camera,
+      camera_target: Vector3::new(0.0, 0.0, 0.0),
       last_mouse_pos: Vector2::new(-1.0, -1.0),
       placing_height: 0.0,
       object_being_placed: None,
@@ -172,54 +286,79 @@ impl EditorScreen {
       mouse_state: MouseState::World,
       selected_model: 0,
       object_selected: 0,
+      multi_selection: Vec::new(),
       known_models: import_export::get_models(&mut logs),
+      known_scripts: import_export::get_scripts(&mut logs),
+      script_templates: script_templates::load_templates(&mut logs),
       run_game: false,
-      f6_released_last_frame: true,
       right_clicked_last_frame: false,
       update_mouse_cursor: false,
       scene_name: "empty_scene".to_string(),
-      load_scene_option: 0,
       logs,
       windows: EditorWindows::new(),
       options: EditorOptions::new(),
       game_options: GameOptions::new(),
       instanced_buffers: Vec::new(),
       instanced_buffers_added: Vec::new(),
-    }
+      destroyed_objects: Vec::new(),
+      script_errors: ScriptErrors::new(),
+      spawned_objects: Vec::new(),
+      removed_objects: Vec::new(),
+      scene_variables: Vec::new(),
+      scene_script: SceneScript::new("empty_scene".to_string()),
+      script_check_timer: 0.0,
+      preferences,
+      scene_browser: SceneBrowser::new(true),
+      brush: ScatterBrush::new(),
+      array_tool: ArrayTool::new(),
+      bookmarks: Vec::new(),
+      bookmark_window: BookmarkWindow::new(),
+      keymap,
+      keymap_location,
+    };
+    
+    editor.apply_preferences();
+    editor
   }
   
-  pub fn new_with_data(window_size: Vector2<f32>, rng: rand::prelude::ThreadRng, camera: PerspectiveCamera, object_being_placed: Option<WorldObject>, scene_name: String, placing_height: f32, world_objects: Vec<WorldObject>, light_objects: Vec<LightObject>, windows: EditorWindows, options: EditorOptions, game_options: GameOptions, run_game: bool, model_sizes: Vec<(String, Vector3<f32>)>, instanced_buffers: Vec<String>) -> EditorScreen {
+  fn apply_preferences(&mut self) {
+    let preferences = &self.preferences;
     
-    let mut logs = Logs::new(window_size);
+    self.options.snap_to_grid = preferences.snap_to_grid;
+    self.options.show_axis = preferences.show_axis;
+    self.options.place_with_mouse = preferences.place_with_mouse;
+    self.options.surface_snap = preferences.surface_snap;
+    self.options.fly_camera = preferences.fly_camera;
+    self.options.euler_order = preferences.euler_order.max(0).min(rotation::EULER_ORDERS.len() as i32-1);
+    self.options.fly_speed = preferences.camera_speed.max(FLY_MIN_SPEED);
+    self.script_errors.pause_on_error = preferences.pause_on_script_error;
+    self.camera.set_move_speed(self.options.fly_speed);
+    self.data.ui_scale = preferences.ui_scale;
     
-    EditorScreen {
-      data: SceneData::new(window_size, model_sizes),
-      rng,
-      camera,
-      last_mouse_pos: Vector2::new(-1.0, -1.0),
-      placing_height,
-      object_being_placed,
-      world_objects,
-      light_objects,
-      mouse_state: MouseState::World,
-      selected_model: 0,
-      object_selected: 0,
-      known_models: import_export::get_models(&mut logs),
-      run_game,
-      f6_released_last_frame: true,
-      right_clicked_last_frame: false,
-      update_mouse_cursor: false,
-      scene_name,
-      load_scene_option: 0,
-      logs,
-      windows,
-      options,
-      game_options,
-      instanced_buffers,
-      instanced_buffers_added: Vec::new(),
+    if let Some(open_windows) = &preferences.open_windows {
+      for (name, open) in self.windows.remembered() {
+        *open = open_windows.iter().any(|window| window == name);
+      }
     }
   }
   
+  fn store_preferences(&mut self) {
+    self.preferences.snap_to_grid = self.options.snap_to_grid;
+    // The axis is hidden while the game runs
+    self.preferences.show_axis = self.options.show_axis || self.run_game;
+    self.preferences.place_with_mouse = self.options.place_with_mouse;
+    self.preferences.surface_snap = self.options.surface_snap;
+    self.preferences.fly_camera = self.options.fly_camera;
+    self.preferences.pause_on_script_error = self.script_errors.pause_on_error;
+    self.preferences.euler_order = self.options.euler_order;
+    self.preferences.camera_speed = self.options.fly_speed;
+    self.preferences.set_ui_scale(self.data.ui_scale);
+    self.preferences.open_windows = Some(self.windows.remembered().into_iter()
+                                           .filter(|(_, open)| **open)
+                                           .map(|(name, _)| name.to_string())
+                                           .collect());
+  }
+  
   pub fn update_input(&mut self, delta_time: f32) {
     self.data.controller.update();
     
@@ -230,23 +369,28 @@ impl EditorScreen {
     let _s_pressed = self.data.keys.s_pressed();
     let _d_pressed = self.data.keys.d_pressed();
     let _r_pressed = self.data.keys.r_pressed();
-    let _f_pressed = self.data.keys.f_pressed();
     
-    let u_pressed = self.data.keys.u_pressed();
-    let j_pressed = self.data.keys.j_pressed();
-    let i_pressed = self.data.keys.i_pressed();
-    let k_pressed = self.data.keys.k_pressed();
-    let o_pressed = self.data.keys.o_pressed();
-    let l_pressed = self.data.keys.l_pressed();
+    // Shortcuts are ignored while typing into a text box
+    let keyboard_free = !self.data.imgui_info.wants_keyboard;
+    let held = |action| keyboard_free && self.keymap.held(action, &self.data);
+    let u_pressed = held(Action::MovePositiveX);
+    let j_pressed = held(Action::MoveNegativeX);
+    let i_pressed = held(Action::MoveUp);
+    let k_pressed = held(Action::MoveDown);
+    let o_pressed = held(Action::MovePositiveZ);
+    let l_pressed = held(Action::MoveNegativeZ);
     
-    let one_pressed = self.data.keys.one_pressed();
     let scroll_delta = self.data.scroll_delta;
     
     let left_clicked = self.data.left_mouse;
     let right_clicked = self.data.right_mouse;
     self.update_mouse_cursor = false;
     
-    if right_clicked {
+    let flying = right_clicked && self.options.fly_camera;
+    
+    if flying {
+      self.fly_camera(mouse, delta_time);
+    } else if right_clicked {
       self.object_being_placed = None;
       self.object_selected = 0;
       if self.last_mouse_pos != Vector2::new(-1.0, -1.0) {
@@ -256,19 +400,9 @@ impl EditorScreen {
         self.camera.process_orbiting_camera_movement(x_offset*-1.0, y_offset);
         
         if !self.right_clicked_last_frame {
-          let mut cam_pos = self.camera.get_position();
-          let mouse_ray = self.camera.mouse_to_world_ray(mouse, self.data.window_dim);
-          if mouse_ray.y < 0.0 {
-            while cam_pos.y > 0.0  {
-              cam_pos += mouse_ray;
-            }
-            // TODO: align with goal height
-            cam_pos -= mouse_ray;
-            cam_pos.y = self.placing_height;
-          }
-          
-          let point_of_rotation = cam_pos;
-          self.camera.set_target(point_of_rotation);
+          let ground = Plane::horizontal(self.placing_height);
+          let point_of_rotation = self.mouse_on_plane(&ground).unwrap_or(self.camera.get_position());
+          self.set_camera_target(point_of_rotation);
         }
         //self.camera.process_mouse_movement_around_point(x_offset, y_offset, point_of_rotation);
        // self.camera.rotate_camera_horizontally(Vector3::new(0.0, 0.0, 0.0), 1.0);
@@ -297,7 +431,11 @@ impl EditorScreen {
       }*/
     }
     
-    self.camera.change_zoom(scroll_delta*-1.0, 100.0*delta_time);
+    if flying {
+      self.options.fly_speed = (self.options.fly_speed*(1.0 + scroll_delta*0.1)).max(FLY_MIN_SPEED);
+    } else {
+      self.camera.change_zoom(scroll_delta*-1.0, 100.0*delta_time);
+    }
     /*
     if w_pressed {
       self.camera.process_movement(camera::Direction::YAlignedForward, delta_time);
@@ -374,7 +512,7 @@ impl EditorScreen {
       }
     }
     
-    if left_clicked {
+    if left_clicked && !self.brush.enabled {
       if let Some(object) = &self.object_being_placed {
         self.world_objects.push(object.clone());
       }
@@ -382,52 +520,536 @@ impl EditorScreen {
       self.object_selected = 0;
     }
     
-    if one_pressed {
-      self.change_selected_object()
+    // Fly mode uses Shift and Ctrl for speed, so chords would fire while moving
+    if keyboard_free && !flying {
+      if self.keymap.triggered(Action::PlaceSelectedModel, &self.data) {
+        self.change_selected_object()
+      }
+      
+      if self.keymap.triggered(Action::FrameSelection, &self.data) {
+        self.frame_selection();
+      }
+      
+      if self.keymap.triggered(Action::ToggleMousePlacement, &self.data) {
+        self.options.place_with_mouse = !self.options.place_with_mouse;
+      }
+      if self.keymap.triggered(Action::ToggleShowAxis, &self.data) {
+        self.options.show_axis = !self.options.show_axis;
+      }
+      if self.keymap.triggered(Action::ToggleSnapToGrid, &self.data) {
+        self.options.snap_to_grid = !self.options.snap_to_grid;
+      }
+      
+      for i in 0..keymap::BOOKMARK_SHORTCUTS {
+        if self.keymap.triggered(Action::GoToBookmark(i), &self.data) {
+          self.go_to_bookmark(i);
+        }
+      }
     }
     
     self.right_clicked_last_frame = right_clicked;
     self.last_mouse_pos = mouse;
   }
   
+  // Mouse look with right mouse held, WASD to move, Q and E to go down and up by default
+  pub fn fly_camera(&mut self, mouse: Vector2<f32>, delta_time: f32) {
+    if self.right_clicked_last_frame && self.last_mouse_pos != Vector2::new(-1.0, -1.0) {
+      let (pitch, yaw) = bookmarks::pitch_yaw_from_front(self.camera_front().normalize());
+      let x_offset = mouse.x - self.last_mouse_pos.x;
+      let y_offset = self.last_mouse_pos.y - mouse.y;
+      self.camera.set_yaw(yaw + x_offset*FLY_LOOK_SENSITIVITY);
+      self.camera.set_pitch((pitch + y_offset*FLY_LOOK_SENSITIVITY).max(-FLY_MAX_PITCH).min(FLY_MAX_PITCH));
+    }
+    
+    if self.data.imgui_info.wants_keyboard {
+      return;
+    }
+    
+    let mut speed = self.options.fly_speed;
+    if self.data.modifiers.shift {
+      speed *= FLY_FAST_MULTIPLIER;
+    }
+    if self.data.modifiers.ctrl {
+      speed *= FLY_SLOW_MULTIPLIER;
+    }
+    self.camera.set_move_speed(speed);
+    
+    let directions = [(Action::FlyForward, camera::Direction::Forward),
+                      (Action::FlyBackward, camera::Direction::Backward),
+                      (Action::FlyLeft, camera::Direction::YAlignedLeft),
+                      (Action::FlyRight, camera::Direction::YAlignedRight),
+                      (Action::FlyUp, camera::Direction::PositiveY),
+                      (Action::FlyDown, camera::Direction::NegativeY)];
+    for (action, direction) in directions.iter() {
+      if self.keymap.held(*action, &self.data) {
+        self.camera.process_movement(*direction, delta_time);
+      }
+    }
+  }
+  
+  pub fn camera_front(&self) -> Vector3<f32> {
+    self.camera.mouse_to_world_ray(self.data.window_dim*0.5, self.data.window_dim)
+  }
+  
+  pub fn selection_position(&self) -> Vector3<f32> {
+    if self.object_selected == 1 {
+      if let Some(object) = &self.object_being_placed {
+        return object.position();
+      }
+    } else if self.object_selected > 1 {
+      return self.world_objects[self.object_selected as usize-2].position();
+    }
+    
+    Vector3::new(0.0, self.placing_height, 0.0)
+  }
+  
+  pub fn placement_plane(&self) -> Plane {
+    self.options.placement_plane.to_plane(self.placing_height, self.selection_position(), self.camera_front())
+  }
+  
+  pub fn mouse_on_plane(&self, plane: &Plane) -> Option<Vector3<f32>> {
+    let mouse_ray = self.camera.mouse_to_world_ray(self.data.mouse_pos, self.data.window_dim);
+    placement::ray_plane_intersection(self.camera.get_position(), mouse_ray, plane)
+  }
+  
+  pub fn model_size(&self, model: &str) -> Vector3<f32> {
+    for (reference, size) in &self.data.model_sizes {
+      if reference == model {
+        return *size;
+      }
+    }
+    
+    Vector3::new(1.0, 1.0, 1.0)
+  }
+  
+  pub fn object_bounds(&self, object: &WorldObject) -> BoundingBox {
+    let model_size = self.model_size(&object.model());
+    let size = object.size();
+    BoundingBox::from_centre(object.position(), Vector3::new(model_size.x*size.x, model_size.y*size.y, model_size.z*size.z))
+  }
+  
+  // Distance from the objects position to the side of its model facing along normal
+  pub fn resting_offset(&self, object: &WorldObject, normal: Vector3<f32>) -> f32 {
+    let model_size = self.model_size(&object.model());
+    let size = object.size();
+    placement::half_extent_along(Vector3::new(model_size.x*size.x, model_size.y*size.y, model_size.z*size.z), normal)
+  }
+  
+  // Point and face normal of whatever the mouse ray hits first, either a world object
+  // or the ground at the placing height
+  pub fn mouse_on_surface(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let origin = self.camera.get_position();
+    let mouse_ray = self.camera.mouse_to_world_ray(self.data.mouse_pos, self.data.window_dim);
+    
+    let mut closest = placement::ray_plane_distance(origin, mouse_ray, &Plane::horizontal(self.placing_height)).map(|t| {
+      (t, origin + mouse_ray*t, Vector3::new(0.0, 1.0, 0.0))
+    });
+    
+    for object in &self.world_objects {
+      let bounds = self.object_bounds(object);
+      if let Some((t, normal)) = placement::ray_box_intersection(origin, mouse_ray, &bounds) {
+        if closest.map_or(true, |(closest_t, _, _)| t < closest_t) {
+          let mut point = origin + mouse_ray*t;
+          if normal.y > 0.5 {
+            point.y = bounds.max.y;
+          }
+          closest = Some((t, point, normal));
+        }
+      }
+    }
+    
+    closest.map(|(_, point, normal)| (point, normal))
+  }
+  
+  // Every object ticked in the World Objects window plus the selected one
+  pub fn selected_indices(&self) -> Vec<usize> {
+    let mut indices = Vec::new();
+    for i in 0..self.world_objects.len() {
+      if self.multi_selection.contains(&self.world_objects[i].id()) || self.object_selected == i as i32+2 {
+        indices.push(i);
+      }
+    }
+    
+    indices
+  }
+  
+  pub fn apply_align(&mut self, action: AlignAction) {
+    let indices = self.selected_indices();
+    let axis = self.options.align_axis as usize;
+    let bounds = indices.iter().map(|i| self.object_bounds(&self.world_objects[*i])).collect::<Vec<BoundingBox>>();
+    
+    let offsets = match action {
+      AlignAction::Align(anchor) => align::align_offsets(&bounds, axis, anchor),
+      AlignAction::Distribute => align::distribute_offsets(&bounds, axis),
+      AlignAction::Mirror => {
+        let plane = {
+          if self.options.mirror_about_selection {
+            align::selection_bounds(&bounds).map_or(0.0, |total| total.centre()[axis])
+          } else {
+            0.0
+          }
+        };
+        
+        for i in &indices {
+          let object = &mut self.world_objects[*i];
+          let position = align::mirror_position(object.position(), axis, plane);
+          let orientation = rotation::mirror_orientation(object.orientation(), axis);
+          object.move_to(position);
+          object.rotate_to(orientation);
+        }
+        
+        return;
+      },
+    };
+    
+    for (i, offset) in indices.iter().zip(offsets) {
+      let mut position = self.world_objects[*i].position();
+      position[axis] += offset;
+      self.world_objects[*i].move_to(position);
+    }
+  }
+  
+  // Moves the camera back along its current view until the selection fills the screen
+  pub fn frame_selection(&mut self) {
+    let mut bounds = self.selected_indices().iter().map(|i| self.object_bounds(&self.world_objects[*i])).collect::<Vec<BoundingBox>>();
+    if let Some(object) = &self.object_being_placed {
+      bounds.push(self.object_bounds(object));
+    }
+    
+    let total = match align::selection_bounds(&bounds) {
+      Some(total) => total,
+      None => return,
+    };
+    
+    let centre = total.centre();
+    let radius = ((total.max - total.min).magnitude()*0.5).max(FRAME_MIN_RADIUS);
+    let distance = radius*FRAME_DISTANCE_SCALE;
+    let front = self.camera_front().normalize();
+    
+    self.camera.set_position(centre - front*distance);
+    self.set_camera_target(centre);
+    self.camera.set_zoom(distance);
+  }
+  
+  pub fn add_bookmark(&mut self, name: String) {
+    let position = self.camera.get_position();
+    let (pitch, yaw) = bookmarks::pitch_yaw_from_front(self.camera_front().normalize());
+    
+    self.bookmarks.push(CameraBookmark::new(name, position, pitch, yaw, self.camera_target));
+  }
+  
+  pub fn go_to_bookmark(&mut self, idx: usize) {
+    if idx >= self.bookmarks.len() {
+      return;
+    }
+    
+    let bookmark = self.bookmarks[idx].clone();
+    self.camera.set_position(bookmark.position);
+    self.camera.set_pitch(bookmark.pitch);
+    self.camera.set_yaw(bookmark.yaw);
+    self.set_camera_target(bookmark.target);
+    self.camera.set_zoom(bookmark.distance);
+  }
+  
+  // The camera can't be asked for its target so it is kept here as well
+  pub fn set_camera_target(&mut self, target: Vector3<f32>) {
+    self.camera_target = target;
+    self.camera.set_target(target);
+  }
+  
+  pub fn open_scene(&mut self, scene_name: String) {
+    let imported = import(scene_name.to_string(), &mut self.logs);
+    self.open_imported_scene(scene_name, imported);
+  }
+  
+  // For a scene that has already been read with import_export::import
+  pub fn open_imported_scene(&mut self, scene_name: String, imported: ImportedScene) {
+    let (load_models, objects, game_options) = imported;
+    for object in &objects {
+      if object.instanced_rendered() {
+        if !self.instanced_buffers_added.contains(&object.model().to_string()) {
+          self.instanced_buffers_added.push(object.model().to_string());
+        }
+      }
+    }
+    
+    // Models preloaded by the load screen are already there
+    let load_models = load_models.into_iter().filter(|(reference, _)| {
+      !self.data.model_sizes.iter().any(|(loaded, _)| loaded == reference)
+    }).collect::<Vec<(String, String)>>();
+    
+    self.world_objects = objects;
+    self.spawned_objects.clear();
+    self.removed_objects.clear();
+    self.multi_selection.clear();
+    self.known_scripts = import_export::get_scripts(&mut self.logs);
+    self.script_templates = script_templates::load_templates(&mut self.logs);
+    self.bookmarks = import_bookmarks(scene_name.to_string(), &mut self.logs);
+    self.scene_variables = import_variables(scene_name.to_string(), &mut self.logs);
+    self.scene_script = SceneScript::new(scene_name.to_string());
+    self.data.models_to_load = load_models;
+    self.game_options = game_options;
+    self.windows.load_window = false;
+    self.scene_name = scene_name.to_string();
+    
+    self.preferences.add_recent_scene(scene_browser::current_project(), scene_name);
+    self.preferences.save(&mut self.logs);
+  }
+  
+  pub fn new_scene(&mut self, scene_name: String) {
+    self.reset();
+    self.scene_script = SceneScript::new(scene_name.to_string());
+    self.scene_name = scene_name;
+  }
+  
+  // Models of the old project stay loaded, only the model list is read again
+  pub fn open_project(&mut self, project: &str) -> bool {
+    if !scene_browser::open_project(project, &mut self.preferences, &mut self.logs) {
+      return false;
+    }
+    
+    self.known_models = import_export::get_models(&mut self.logs);
+    self.known_scripts = import_export::get_scripts(&mut self.logs);
+    self.script_templates = script_templates::load_templates(&mut self.logs);
+    self.reset();
+    true
+  }
+  
   pub fn reset(&mut self) {
     self.world_objects.clear();
+    self.spawned_objects.clear();
+    self.removed_objects.clear();
+    self.bookmarks.clear();
+    self.multi_selection.clear();
     self.placing_height = 0.0;
     self.object_being_placed = None;
     self.mouse_state = MouseState::World;
     self.selected_model = 0;
     self.object_selected = 0;
     self.run_game = false;
-    self.f6_released_last_frame = true;
     self.scene_name = "new_scene".to_string();
-    self.load_scene_option = 0;
+    self.scene_variables.clear();
+    self.scene_script = SceneScript::new(self.scene_name.to_string());
     self.windows.load_window = false;
     
     self.camera = PerspectiveCamera::default_vk();
     self.camera.set_position(Vector3::new(CAMERA_DEFAULT_X, CAMERA_DEFAULT_Y, CAMERA_DEFAULT_Z));
     self.camera.set_pitch(CAMERA_DEFAULT_PITCH);
     self.camera.set_yaw(CAMERA_DEFAULT_YAW);
-    self.camera.set_move_speed(CAMERA_DEFAULT_SPEED);
+    self.camera.set_move_speed(self.options.fly_speed);
+    self.camera_target = Vector3::new(0.0, 0.0, 0.0);
   }
   
-  pub fn change_selected_object(&mut self) {
-    let id = {
-      if self.world_objects.len() > 0 {
-        self.world_objects[self.world_objects.len()-1].id()+1
-      } else {
-        0
+  // Objects scripts spawned or destroyed, then picks up what scripts did to the rest
+  fn apply_script_changes(&mut self, lua: &mut Lua) {
+    for id in scripting::take_destroyed(lua, &mut self.logs) {
+      if let Some(idx) = self.world_objects.iter().position(|object| object.id() == id) {
+        let mut object = self.world_objects.remove(idx);
+        object.call_script(lua, Callback::Destroy, 0.0, &mut self.script_errors);
+        if self.spawned_objects.contains(&id) {
+          self.spawned_objects.retain(|spawned| *spawned != id);
+        } else {
+          self.removed_objects.push(object);
+        }
+        
+        // object_selected is an index into world_objects offset by 2
+        if self.object_selected == idx as i32+2 {
+          self.object_selected = 0;
+        } else if self.object_selected > idx as i32+2 {
+          self.object_selected -= 1;
+        }
       }
-    };
+    }
+    
+    for object in scripting::take_spawned(lua, &mut self.logs) {
+      let location = self.known_models.iter()
+                       .find(|(reference, _, _)| *reference == object.model)
+                       .map(|(_, location, _)| location.to_string());
+      match location {
+        Some(location) => {
+          if !self.data.model_sizes.iter().any(|(reference, _)| *reference == object.model) &&
+             !self.data.models_to_load.iter().any(|(reference, _)| *reference == object.model) {
+            self.data.models_to_load.push((object.model.to_string(), location.to_string()));
+          }
+          
+          self.spawned_objects.push(object.id);
+          let world_object = WorldObject::from_script_object(object, location, self.scene_name.to_string());
+          world_object.write_script_state(lua);
+          self.world_objects.push(world_object);
+        },
+        None => {
+          self.logs.add_error("scene.spawn: unknown model ".to_owned() + &object.model);
+          scripting::remove_object(lua, object.id);
+        }
+      }
+    }
+    
+    for object in &mut self.world_objects {
+      object.read_script_state(lua, &mut self.script_errors);
+    }
+  }
+  
+  // New errors go in log.ini once, the panel opens instead of the error popup
+  fn handle_script_errors(&mut self) {
+    let new_errors = self.script_errors.take_new_errors();
+    if new_errors.len() > 0 {
+      self.windows.script_errors = true;
+    }
+    for e in new_errors {
+      self.logs.write_error(e);
+    }
     
+    if let Some(object_id) = self.script_errors.take_pause() {
+      self.game_options.paused = true;
+      if let Some(id) = object_id {
+        self.select_object(id);
+      }
+    }
+  }
+  
+  fn selected_object_id(&self) -> Option<u32> {
+    if self.object_selected > 1 {
+      self.world_objects.get(self.object_selected as usize-2).map(|object| object.id())
+    } else {
+      None
+    }
+  }
+  
+  fn select_object(&mut self, id: u32) {
+    if let Some(idx) = self.world_objects.iter().position(|object| object.id() == id) {
+      self.object_selected = idx as i32+2;
+    }
+  }
+  
+  // Spawned objects go and destroyed ones come back, like reset does for transforms
+  fn undo_script_changes(&mut self) {
+    let spawned_objects = &self.spawned_objects;
+    self.world_objects.retain(|object| !spawned_objects.contains(&object.id()));
+    self.spawned_objects.clear();
+    
+    if self.removed_objects.len() > 0 {
+      self.world_objects.append(&mut self.removed_objects);
+      self.world_objects.sort_by_key(|object| object.id());
+    }
+    
+    if self.object_selected > 1 && self.object_selected as usize-2 >= self.world_objects.len() {
+      self.object_selected = 0;
+    }
+  }
+  
+  pub fn next_object_id(&self) -> u32 {
+    if self.world_objects.len() > 0 {
+      self.world_objects[self.world_objects.len()-1].id()+1
+    } else {
+      0
+    }
+  }
+  
+  // Reference and location of the model picked in the Loaded Models window
+  pub fn selected_model_details(&self) -> Option<(String, String)> {
     if self.data().model_sizes.len() > self.selected_model as usize {
       let (model_name, _) = self.data().model_sizes[self.selected_model as usize].clone();
       for i in 0..self.known_models.len() {
         if model_name.to_string() == self.known_models[i].0 {
-          let location = self.known_models[i].1.clone();
-          self.object_being_placed = Some(WorldObject::new_empty(id, model_name.to_string(), location, self.scene_name.to_string()));
-          self.object_selected = 1;
+          return Some((model_name.to_string(), self.known_models[i].1.clone()));
+        }
+      }
+    }
+    
+    None
+  }
+  
+  pub fn change_selected_object(&mut self) {
+    let id = self.next_object_id();
+    
+    if let Some((model_name, location)) = self.selected_model_details() {
+      self.object_being_placed = Some(WorldObject::new_empty(id, model_name, location, self.scene_name.to_string()));
+      self.object_selected = 1;
+    }
+  }
+  
+  pub fn update_brush(&mut self) {
+    if !self.data.left_mouse {
+      return;
+    }
+    
+    let centre = match self.mouse_on_plane(&Plane::horizontal(self.placing_height)) {
+      Some(centre) => centre,
+      None => return,
+    };
+    
+    let details = self.selected_model_details();
+    
+    if self.brush.erase {
+      // Erasing a single model needs to know which one
+      let erase_model = match (&details, self.brush.erase_all_models) {
+        (_, true) => None,
+        (Some((model, _)), false) => Some(model.to_string()),
+        (None, false) => return,
+      };
+      
+      // Same as deleting each object from the inspector
+      let mut erased = false;
+      let mut i = 0;
+      while i < self.world_objects.len() {
+        let object = &self.world_objects[i];
+        if self.brush.in_radius(centre, object.position()) &&
+           erase_model.as_ref().map_or(true, |model| object.model() == *model) {
+          let mut object = self.world_objects.remove(i);
+          object.delete_script(&mut self.logs);
+          if self.run_game {
+            self.destroyed_objects.push(object);
+          }
+          erased = true;
+        } else {
+          i += 1;
         }
       }
+      
+      if erased && self.object_selected > 1 {
+        self.object_selected = 0;
+      }
+      return;
+    }
+    
+    let (model, location) = match details {
+      Some(details) => details,
+      None => return,
+    };
+    
+    let spacing_radius = self.brush.radius + self.brush.min_spacing;
+    let existing = self.world_objects.iter()
+                     .filter(|object| object.model() == model)
+                     .map(|object| object.position())
+                     .filter(|position| brush::distance_xz(centre, *position) <= spacing_radius)
+                     .collect::<Vec<Vector3<f32>>>();
+    
+    let stamps = self.brush.scatter(&mut self.rng, centre, &existing);
+    
+    let model_height = self.model_size(&model).y;
+    let instanced = self.instanced_buffers.contains(&model) || self.instanced_buffers_added.contains(&model);
+    for stamp in stamps {
+      let id = self.next_object_id();
+      let mut position = stamp.position;
+      position.y += model_height*stamp.scale*0.5;
+      self.world_objects.push(WorldObject::new_with_data(id, model.to_owned() + &id.to_string(), self.scene_name.to_string(),
+                                                         model.to_string(), location.to_string(),
+                                                         position,
+                                                         stamp.rotation,
+                                                         Vector3::new(stamp.scale, stamp.scale, stamp.scale),
+                                                         instanced));
+    }
+  }
+  
+  pub fn apply_array(&mut self) {
+    if self.object_selected < 2 {
+      return;
+    }
+    
+    let original = self.world_objects[self.object_selected as usize-2].clone();
+    for (position, orientation) in self.array_tool.copies(original.position(), original.orientation()) {
+      let id = self.next_object_id();
+      self.world_objects.push(original.copy_to(id, position, orientation));
     }
   }
   
@@ -437,80 +1059,26 @@ impl EditorScreen {
       self.mut_data().imgui_info.wants_keyboard = ui.want_capture_keyboard();
       
       if self.windows.load_window {
-        if let Err(e) = fs::create_dir_all("./Scenes") {
-          self.logs.add_error(e.to_string());
-        }
-        
-        let paths = fs::read_dir("./Scenes/").unwrap();
-        
-        let mut scenes = Vec::new();
-        
-        for path in paths {
-          scenes.push(ImString::new(path.unwrap().path().display().to_string()));
-        }
-        
-        let mut should_load = false;
-        let mut should_cancel = false;
-        let mut new = false;
-        ui.window(im_str!("Load Scene"))
-          .size([500.0, 100.0], Condition::Appearing)
-          .always_auto_resize(true)
-          .collapsible(false)
-          .build( || {
-            let items: Vec<_> = scenes.iter().map(|p| 
-              p//.as_ref()
-            ).collect();
-            
-            ui.text("Scene: ");
-            ui.same_line(0.0);
-            ui.combo(im_str!(""), &mut self.load_scene_option, &items[..], -1);
-            should_cancel = ui.button(im_str!("Cancel"), [0.0, 0.0]);
-            ui.same_line(0.0);
-            should_load = ui.button(im_str!("Load"), [0.0,0.0]);
-            ui.same_line(400.0);
-            new = ui.button(im_str!("New"), [0.0,0.0]);
-          });
-        
-        if new {
-          self.reset();
-          self.windows.load_window = false;
-          return;
-        }
-        
-        if should_cancel {
-          self.windows.load_window = false;
-          return;
-        }
-        
-        if should_load {
-          if self.load_scene_option as usize+1 > scenes.len() {
-            self.windows.load_window = false;
-            return;
-          }
-          
-          let mut path = scenes[self.load_scene_option as usize].to_str().to_string();
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          path.remove(0);
-          let (load_models, objects, game_options) = import(path.to_string(), &mut self.logs);
-          for object in &objects {
-            if object.instanced_rendered() {
-              if !self.instanced_buffers_added.contains(&object.model().to_string()) {
-                self.instanced_buffers_added.push(object.model().to_string());
-              }
+        match self.scene_browser.update(Some(*ui), self.data.window_dim) {
+          Some(BrowserAction::New(name)) => {
+            self.new_scene(name);
+          },
+          Some(BrowserAction::Open(project, scene)) => {
+            if project == scene_browser::current_project() || self.open_project(&project) {
+              self.open_scene(scene);
             }
-          }
-          self.world_objects = objects;
-          self.data.models_to_load = load_models;
-          self.game_options = game_options;
-          self.windows.load_window = false;
-          self.scene_name = path.to_string();
+          },
+          Some(BrowserAction::OpenFolder(project)) => {
+            if self.open_project(&project) {
+              // reset closes the browser, stay on it to pick a scene from the new project
+              self.windows.load_window = true;
+              self.scene_browser.refresh(&self.preferences, &mut self.logs);
+            }
+          },
+          Some(BrowserAction::Cancel) => {
+            self.windows.load_window = false;
+          },
+          None => {},
         }
         
         return;
@@ -523,18 +1091,42 @@ impl EditorScreen {
       
       ui.main_menu_bar(|| {
         ui.menu(im_str!("File")).build(|| {
-          ui.menu_item(im_str!("New")).selected(&mut should_new).build();
-          ui.menu_item(im_str!("Save")).selected(&mut should_save).build();
-          ui.menu_item(im_str!("Load")).selected(&mut should_load).build();
+          ui.menu_item(im_str!("New")).shortcut(&ImString::new(self.keymap.shortcut(Action::NewScene))).selected(&mut should_new).build();
+          ui.menu_item(im_str!("Save")).shortcut(&ImString::new(self.keymap.shortcut(Action::SaveScene))).selected(&mut should_save).build();
+          ui.menu_item(im_str!("Load")).shortcut(&ImString::new(self.keymap.shortcut(Action::LoadScene))).selected(&mut should_load).build();
           ui.menu_item(im_str!("Exit")).selected(&mut should_exit).build();
         });
         ui.menu(im_str!("Edit Options")).build(|| {
-          ui.menu_item(im_str!("Mouse Placement")).shortcut(im_str!("Ctrl+M")).selected(&mut self.options.place_with_mouse).build();
-          ui.menu_item(im_str!("Show Axis")).shortcut(im_str!("Ctrl+A")).selected(&mut self.options.show_axis).build();
-          ui.menu_item(im_str!("Snap to grid")).shortcut(im_str!("Ctrl+G")).selected(&mut self.options.snap_to_grid).build();
+          ui.menu_item(im_str!("Mouse Placement")).shortcut(&ImString::new(self.keymap.shortcut(Action::ToggleMousePlacement))).selected(&mut self.options.place_with_mouse).build();
+          ui.menu_item(im_str!("Show Axis")).shortcut(&ImString::new(self.keymap.shortcut(Action::ToggleShowAxis))).selected(&mut self.options.show_axis).build();
+          ui.menu_item(im_str!("Snap to grid")).shortcut(&ImString::new(self.keymap.shortcut(Action::ToggleSnapToGrid))).selected(&mut self.options.snap_to_grid).build();
+          ui.menu_item(im_str!("Surface Snapping")).selected(&mut self.options.surface_snap).build();
+          ui.menu_item(im_str!("Fly Camera (hold right mouse)")).selected(&mut self.options.fly_camera).build();
+          ui.push_item_width(120.0);
+          ui.slider_float(im_str!("Fly Speed"), &mut self.options.fly_speed, FLY_MIN_SPEED, 500.0).build();
+          ui.slider_float(im_str!("UI Scale"), &mut self.data.ui_scale, 0.5, 3.0).build();
+          ui.push_item_width(0.0);
+          ui.menu(im_str!("Rotation Order (degrees)")).build(|| {
+            for i in 0..rotation::EULER_ORDERS.len() {
+              let mut selected = self.options.euler_order == i as i32;
+              if ui.menu_item(&ImString::new(rotation::EULER_ORDERS[i].name())).selected(&mut selected).build() {
+                self.options.euler_order = i as i32;
+              }
+            }
+          });
+          ui.menu(im_str!("Placement Plane")).build(|| {
+            for plane in &[PlacementPlane::Ground, PlacementPlane::Vertical, PlacementPlane::CameraFacing] {
+              let mut selected = self.options.placement_plane == *plane;
+              if ui.menu_item(&ImString::new(plane.name())).selected(&mut selected).build() {
+                self.options.placement_plane = *plane;
+              }
+            }
+          });
         });
         ui.menu(im_str!("Run Options")).build(|| {
-          ui.menu_item(im_str!("Run")).shortcut(im_str!("F6")).selected(&mut self.run_game).build();
+          ui.menu_item(im_str!("Run")).shortcut(&ImString::new(self.keymap.shortcut(Action::ToggleRun))).selected(&mut self.run_game).build();
+          ui.menu_item(im_str!("Pause")).enabled(self.run_game).selected(&mut self.game_options.paused).build();
+          ui.menu_item(im_str!("Pause on Script Error")).selected(&mut self.script_errors.pause_on_error).build();
         });
         ui.menu(im_str!("Windows")).build(|| {
           ui.menu_item(im_str!("Scene Details")).selected(&mut self.windows.scene_details).build();
@@ -543,9 +1135,21 @@ impl EditorScreen {
           ui.menu_item(im_str!("World Objects")).selected(&mut self.windows.world_objects).build();
           ui.menu_item(im_str!("Camera Options")).selected(&mut self.windows.camera_options).build();
           ui.menu_item(im_str!("Light Options")).selected(&mut self.windows.lights).build();
+          ui.menu_item(im_str!("Scatter Brush")).selected(&mut self.windows.scatter_brush).build();
+          ui.menu_item(im_str!("Array Tool")).selected(&mut self.windows.array_tool).build();
+          ui.menu_item(im_str!("Align Objects")).selected(&mut self.windows.align_tools).build();
+          ui.menu_item(im_str!("Camera Bookmarks")).selected(&mut self.windows.bookmarks).build();
+          ui.menu_item(im_str!("Keyboard Shortcuts")).selected(&mut self.windows.shortcuts).build();
+          ui.menu_item(im_str!("Script Errors")).selected(&mut self.windows.script_errors).build();
         });
       });
       
+      if !self.data.imgui_info.wants_keyboard && !self.data.right_mouse {
+        should_new |= self.keymap.triggered(Action::NewScene, &self.data);
+        should_save |= self.keymap.triggered(Action::SaveScene, &self.data);
+        should_load |= self.keymap.triggered(Action::LoadScene, &self.data);
+      }
+      
       if should_new {
         self.reset();
       }
@@ -555,15 +1159,30 @@ impl EditorScreen {
           object.save_script(self.scene_name.to_string(), &mut self.logs);
         }
         export(self.scene_name.to_string(), &self.world_objects, &self.game_options, &mut self.logs);
+        export_bookmarks(self.scene_name.to_string(), &self.bookmarks, &mut self.logs);
+        export_variables(self.scene_name.to_string(), &self.scene_variables, &mut self.logs);
+        self.scene_script.save(self.scene_name.to_string(), &mut self.logs);
+        self.preferences.add_recent_scene(scene_browser::current_project(), self.scene_name.to_string());
+        self.preferences.save(&mut self.logs);
         self.windows.saved = true;
       }
       if should_load {
+        self.scene_browser.refresh(&self.preferences, &mut self.logs);
         self.windows.load_window = true;
       }
       if should_exit {
         self.data.should_close = true;
       }
       
+      if self.windows.script_errors {
+        let selected_id = self.selected_object_id();
+        match self.script_errors.update(Some(*ui), selected_id, self.data.window_dim) {
+          Some(ScriptErrorAction::Select(id)) => self.select_object(id),
+          Some(ScriptErrorAction::Clear) => self.script_errors.clear(),
+          None => {},
+        }
+      }
+      
       if self.run_game {
         return;
       }
@@ -572,9 +1191,13 @@ impl EditorScreen {
         let mut imstr_scene_name = ImString::with_capacity(32);
         imstr_scene_name.push_str(&self.scene_name);
         
+        let project = scene_browser::current_project();
+        let was_default = self.preferences.default_scene == Some(RecentScene { project: project.to_string(), scene: self.scene_name.to_string() });
+        let mut is_default = was_default;
+        
         ui.window(im_str!("Scene Details"))
-          .size([250.0, 60.0], Condition::Appearing)
-          .position([0.0, 55.0], Condition::Appearing)
+          .size([250.0, 60.0], Condition::FirstUseEver)
+          .position([0.0, 55.0], Condition::FirstUseEver)
           .always_auto_resize(true)
           .build( || {
             ui.text("Scene name:");
@@ -582,33 +1205,129 @@ impl EditorScreen {
             ui.push_item_width(150.0);
             ui.input_text(im_str!(""), &mut imstr_scene_name).build();
             ui.push_item_width(0.0);
+            ui.checkbox(im_str!("Open at startup"), &mut is_default);
+            if self.scene_script.exists() {
+              ui.text("Script: ".to_owned() + scene_script::SCENE_SCRIPT_FILE);
+              ui.same_line(0.0);
+              if ui.button(im_str!("Open##scene_script"), [0.0, 0.0]) {
+                self.scene_script.open(&mut self.logs);
+              }
+            } else if ui.button(im_str!("Create Scene Script"), [0.0, 0.0]) {
+              self.scene_script.create(&mut self.logs);
+            }
+            ui.separator();
+            scene_script::edit_variables(ui, &mut self.scene_variables);
+            ui.separator();
              if ui.button(im_str!("Delete Scene"), [0.0, 0.0]) {
                self.world_objects.clear();
+               self.spawned_objects.clear();
+               self.removed_objects.clear();
+               self.scene_variables.clear();
+               self.scene_script = SceneScript::new("empty_scene".to_string());
                self.placing_height = 0.0;
                self.object_being_placed = None;
                self.mouse_state = MouseState::World;
                self.selected_model = 0;
                self.object_selected = 0;
                self.run_game = false;
-               self.f6_released_last_frame = true;
                let mut new_scene = ImString::with_capacity(32);
                new_scene.push_str("empty_scene");
                imstr_scene_name = new_scene;
-               self.load_scene_option = 0;
                
-               if let Err(e) = fs::remove_dir_all("./Scenes/".to_owned() + &self.scene_name) {
+               if let Err(e) = fs::remove_dir_all(project_file("Scenes/") + &self.scene_name) {
                  self.logs.add_error(e.to_string());
                }
              }
           });
           
         self.scene_name = imstr_scene_name.to_str().to_string();
+        
+        if is_default != was_default {
+          self.preferences.default_scene = {
+            if is_default {
+              Some(RecentScene { project, scene: self.scene_name.to_string() })
+            } else {
+              None
+            }
+          };
+          self.preferences.save(&mut self.logs);
+        }
+      }
+      
+      if self.windows.scatter_brush {
+        self.brush.update(Some(*ui), self.data.window_dim);
+      } else {
+        self.brush.enabled = false;
+      }
+      
+      if self.windows.array_tool && self.object_selected > 1 {
+        if self.array_tool.update(Some(*ui), self.data.window_dim) {
+          self.apply_array();
+        }
+      }
+      
+      if self.windows.bookmarks {
+        let shortcuts = (0..keymap::BOOKMARK_SHORTCUTS).map(|i| self.keymap.shortcut(Action::GoToBookmark(i))).collect::<Vec<String>>();
+        match self.bookmark_window.update(Some(*ui), &self.bookmarks, &shortcuts, self.data.window_dim) {
+          Some(BookmarkAction::Add(name)) => self.add_bookmark(name),
+          Some(BookmarkAction::Go(idx)) => self.go_to_bookmark(idx),
+          Some(BookmarkAction::Remove(idx)) => {
+            self.bookmarks.remove(idx);
+          },
+          None => {},
+        }
+      }
+      
+      if self.windows.shortcuts {
+        if self.keymap.update(Some(*ui), &self.keymap_location, self.data.window_dim) {
+          self.keymap = Keymap::load(&self.keymap_location, &mut self.logs);
+        }
+      }
+      
+      if self.windows.align_tools {
+        let mut action = None;
+        let selected_count = self.selected_indices().len();
+        
+        ui.window(im_str!("Align Objects"))
+          .always_auto_resize(true)
+          .position([220.0, 55.0], Condition::FirstUseEver)
+          .build(|| {
+            ui.text(im_str!("{} objects selected", selected_count));
+            ui.text("Axis:");
+            ui.same_line(0.0);
+            ui.push_item_width(80.0);
+            ui.combo(im_str!("##align_axis"), &mut self.options.align_axis, &[im_str!("X"), im_str!("Y"), im_str!("Z")], -1);
+            ui.push_item_width(0.0);
+            if ui.button(im_str!("Align Min"), [0.0, 0.0]) {
+              action = Some(AlignAction::Align(AlignAnchor::Min));
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("Align Centre"), [0.0, 0.0]) {
+              action = Some(AlignAction::Align(AlignAnchor::Centre));
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("Align Max"), [0.0, 0.0]) {
+              action = Some(AlignAction::Align(AlignAnchor::Max));
+            }
+            if ui.button(im_str!("Distribute"), [0.0, 0.0]) {
+              action = Some(AlignAction::Distribute);
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("Mirror"), [0.0, 0.0]) {
+              action = Some(AlignAction::Mirror);
+            }
+            ui.checkbox(im_str!("Mirror about selection centre"), &mut self.options.mirror_about_selection);
+          });
+        
+        if let Some(action) = action {
+          self.apply_align(action);
+        }
       }
       
       if self.windows.world_objects {
         ui.window(im_str!("World Objects"))
-          .size([200.0, 400.0], Condition::Appearing)
-          .position([0.0, 140.0], Condition::Appearing)
+          .size([200.0, 400.0], Condition::FirstUseEver)
+          .position([0.0, 140.0], Condition::FirstUseEver)
           .build(|| {
             ui.text("None");
             ui.same_line(0.0);
@@ -618,6 +1337,16 @@ impl EditorScreen {
             ui.radio_button(im_str!("Key 1##1"), &mut self.object_selected, 1);
             let mut should_delete_object = false;
             for i in 0..self.world_objects.len() {
+              let id = self.world_objects[i].id();
+              let mut multi_selected = self.multi_selection.contains(&id);
+              if ui.checkbox(&im_str!("##multi{}", i), &mut multi_selected) {
+                if multi_selected {
+                  self.multi_selection.push(id);
+                } else {
+                  self.multi_selection.retain(|selected| *selected != id);
+                }
+              }
+              ui.same_line(0.0);
               ui.text(im_str!("{}: {}", self.world_objects[i].id(), self.world_objects[i].name()));
               ui.same_line(0.0);
               ui.radio_button(&im_str!("##{}", i+2), &mut self.object_selected, i as i32+2);
@@ -629,7 +1358,10 @@ impl EditorScreen {
             
             if should_delete_object {
               self.world_objects[self.object_selected as usize-2].delete_script(&mut self.logs);
-              self.world_objects.remove(self.object_selected as usize-2);
+              let object = self.world_objects.remove(self.object_selected as usize-2);
+              if self.run_game {
+                self.destroyed_objects.push(object);
+              }
               self.object_selected = 0;
             }
           });
@@ -640,8 +1372,8 @@ impl EditorScreen {
         
         let window_width = 200.0;
         ui.window(im_str!("Model List ./Models/*"))
-          .position([self.data.window_dim.x-window_width*1.1, 32.0], Condition::Appearing)
-          .size([window_width, 400.0], Condition::Appearing)
+          .position([self.data.window_dim.x-window_width*1.1, 32.0], Condition::FirstUseEver)
+          .size([window_width, 400.0], Condition::FirstUseEver)
           .build(|| {
             if ui.button(im_str!("Load All"), [0.0, 0.0]) {
               should_load_all = true;
@@ -686,8 +1418,8 @@ impl EditorScreen {
       
       if self.windows.loaded_models {
         ui.window(im_str!("Loaded Models"))
-          .position([0.0, 540.0], Condition::Appearing)
-          .size([200.0, 400.0], Condition::Appearing)
+          .position([0.0, 540.0], Condition::FirstUseEver)
+          .size([200.0, 400.0], Condition::FirstUseEver)
           //.always_auto_resize(true)
         .build(|| {
           let old_selection = self.selected_model;
@@ -707,7 +1439,7 @@ impl EditorScreen {
       if self.windows.camera_options {
         ui.window(im_str!("Game Camera"))
           .always_auto_resize(true)
-          .position([self.data.window_dim.x - 500.0, 25.0], Condition::Appearing)
+          .position([self.data.window_dim.x - 500.0, 25.0], Condition::FirstUseEver)
           .build(|| {
              ui.text("Camera Type:");
              ui.same_line(0.0);
@@ -768,8 +1500,8 @@ impl EditorScreen {
       
        ui.window(im_str!("Instanced Options"))
             .always_auto_resize(true)
-            .size([200.0, 200.0], Condition::Appearing)
-            .position([self.data.window_dim.x - 500.0, 200.0], Condition::Appearing)
+            .size([200.0, 200.0], Condition::FirstUseEver)
+            .position([self.data.window_dim.x - 500.0, 200.0], Condition::FirstUseEver)
             .build(|| {
                             
               ui.text("Existing Buffers");
@@ -849,23 +1581,16 @@ impl Scene for EditorScreen {
     &mut self.data
   }
   
-  fn future_scene(&mut self, window_size: Vector2<f32>) -> Box<Scene> {
-    if self.data().window_resized {
-      Box::new(EditorScreen::new_with_data(window_size, self.rng.clone(), self.camera.clone(), 
-                                           self.object_being_placed.clone(), self.scene_name.to_string(), 
-                                           self.placing_height, self.world_objects.clone(), self.light_objects.clone(), 
-                                           self.windows.clone(), self.options.clone(), self.game_options.clone(),
-                                           self.run_game, self.data.model_sizes.clone(), self.instanced_buffers.clone()))
-    } else {
-      Box::new(EditorScreen::new(window_size, self.data.model_sizes.clone()))
-    }
+  fn resized(&mut self, window_size: Vector2<f32>) {
+    self.logs.set_window_size(window_size);
+  }
+  
+  fn closing(&mut self) {
+    self.store_preferences();
+    self.preferences.save(&mut self.logs);
   }
   
   fn update(&mut self, ui: Option<&Ui>, mut lua: Option<&mut Lua>, delta_time: f32) {
-    if self.data.window_resized {
-      self.data.next_scene = true;
-    }
-    
     for buffer in &self.instanced_buffers_added {
       self.instanced_buffers.push(buffer.to_string());
     }
@@ -873,21 +1598,54 @@ impl Scene for EditorScreen {
     self.instanced_buffers_added.clear();
     
     {
-      let f6_pressed = self.data().keys.f6_pressed();
+      let toggle_run = self.keymap.triggered(Action::ToggleRun, &self.data) && !self.data.imgui_info.wants_keyboard;
       
       let should_run = self.run_game;
       self.draw_imgui(ui);
-      if f6_pressed && self.f6_released_last_frame {
+      if toggle_run {
         self.run_game = !self.run_game;
       }
       
+      if let Some(lua) = &mut lua {
+        for mut object in self.destroyed_objects.drain(..) {
+          object.call_script(lua, Callback::Destroy, 0.0, &mut self.script_errors);
+          scripting::remove_object(lua, object.id());
+        }
+      }
+      
       // Load scripts if went from edit to game run
       if self.run_game && !should_run {
-        for object in &mut self.world_objects {
-          object.load_script();
+        self.script_errors.clear();
+        self.game_options.paused = false;
+        if let Some(lua) = &mut lua {
+          let next_id = self.world_objects.iter().map(|object| object.id()+1).max().unwrap_or(0);
+          scripting::reset(lua, next_id, &mut self.logs);
+          scripting::write_variables(lua, &self.scene_variables, &mut self.logs);
+          // Every object is visible to scripts before any of them are loaded
+          for object in &self.world_objects {
+            object.write_script_state(lua);
+          }
+          self.scene_script.load(lua, &mut self.script_errors);
+          for object in &mut self.world_objects {
+            object.load_script(lua, &mut self.script_errors);
+          }
+          // Every script is loaded before any init runs, the scene's goes first
+          self.scene_script.call(lua, Callback::Init, 0.0, &mut self.script_errors);
+          for object in &mut self.world_objects {
+            object.call_script(lua, Callback::Init, 0.0, &mut self.script_errors);
+          }
+          self.apply_script_changes(lua);
         }
+        self.game_options.fixed_timer = 0.0;
       // Reset positions if went from game run to edit
       } else if !self.run_game && should_run {
+        if let Some(lua) = &mut lua {
+          for object in &mut self.world_objects {
+            object.call_script(lua, Callback::Destroy, 0.0, &mut self.script_errors);
+          }
+          self.scene_script.call(lua, Callback::Destroy, 0.0, &mut self.script_errors);
+        }
+        self.undo_script_changes();
         for object in &mut self.world_objects {
           object.reset();
         }
@@ -904,36 +1662,66 @@ impl Scene for EditorScreen {
       self.object_being_placed = None;
     }
     
+    self.handle_script_errors();
+    
     match self.run_game {
       true => {
         if self.game_options.first_game_loop {
-          for object in &mut self.world_objects {
-            object.load_script();
-          }
           self.camera.set_zoom(self.game_options.camera_distance);
           self.options.show_axis = false;
           self.game_options.first_game_loop = false;
         }
         
-        if let Some(lua) = &mut lua {
+        self.script_check_timer += delta_time;
+        if let (Some(lua), true) = (&mut lua, self.script_check_timer >= scripting::SCRIPT_CHECK_INTERVAL) {
+          self.script_check_timer = 0.0;
+          let mut times = ModifiedTimes::new();
+          self.scene_script.reload_changed(lua, &mut times, &mut self.script_errors);
+          for object in &mut self.world_objects {
+            object.reload_changed_script(lua, &mut times, &mut self.script_errors);
+          }
+        }
+        
+        // Scripts don't run while paused
+        if let (Some(lua), false) = (&mut lua, self.game_options.paused) {
           lua.set("delta_time", delta_time);
+          lua.set("window_dim_x", self.data.window_dim.x);
+          lua.set("window_dim_y", self.data.window_dim.y);
+          // Old scripts read these instead of input
           lua.set("mouse_x", self.data.mouse_pos.x);
           lua.set("mouse_y", self.data.mouse_pos.y);
           lua.set("left_mouse", self.data.left_mouse);
           lua.set("right_mouse", self.data.right_mouse);
-          lua.set("window_dim_x", self.data.window_dim.x);
-          lua.set("window_dim_y", self.data.window_dim.y);
-          
           lua.set("w_key", self.data.keys.w_pressed());
           lua.set("a_key", self.data.keys.a_pressed());
           lua.set("s_key", self.data.keys.s_pressed());
           lua.set("d_key", self.data.keys.d_pressed());
+          scripting::write_input(lua, &self.data.script_input(), &mut self.logs);
+          
+          for object in &self.world_objects {
+            object.write_script_state(lua);
+          }
+          
+          self.scene_script.call(lua, Callback::Update, delta_time, &mut self.script_errors);
+          for object in &mut self.world_objects {
+            object.call_script(lua, Callback::Update, delta_time, &mut self.script_errors);
+          }
+          
+          let max_fixed_time = scripting::FIXED_TIME_STEP*scripting::MAX_FIXED_STEPS as f32;
+          self.game_options.fixed_timer = (self.game_options.fixed_timer + delta_time).min(max_fixed_time);
+          while self.game_options.fixed_timer >= scripting::FIXED_TIME_STEP {
+            self.game_options.fixed_timer -= scripting::FIXED_TIME_STEP;
+            self.scene_script.call(lua, Callback::FixedUpdate, scripting::FIXED_TIME_STEP, &mut self.script_errors);
+            for object in &mut self.world_objects {
+              object.call_script(lua, Callback::FixedUpdate, scripting::FIXED_TIME_STEP, &mut self.script_errors);
+            }
+          }
+          
+          self.apply_script_changes(lua);
         }
         
         let mut i = 0;
-        for world_object in &mut self.world_objects {
-          world_object.update_game(&mut lua, &mut self.logs);
-          
+        for world_object in &self.world_objects {
           if i == self.game_options.camera_target {
             self.camera.set_target(world_object.position());
           }
@@ -956,8 +1744,6 @@ impl Scene for EditorScreen {
           }
         }
         
-        let mouse = self.data.mouse_pos;
-        
         match self.mouse_state {
           MouseState::Ui => {
             
@@ -965,36 +1751,76 @@ impl Scene for EditorScreen {
           MouseState::World => {
             self.update_input(delta_time);
             
+            if self.brush.enabled {
+              self.update_brush();
+            }
+            
             if self.options.place_with_mouse {
-              let mut cam_pos = self.camera.get_position();
-              let mouse_ray = self.camera.mouse_to_world_ray(mouse, self.data.window_dim);
-              if mouse_ray.y < 0.0 {
-                while cam_pos.y > 0.0  {
-                  cam_pos += mouse_ray;
+              let target = {
+                if self.options.surface_snap {
+                  self.mouse_on_surface().map(|(surface, normal)| {
+                    let offset = match &self.object_being_placed {
+                      Some(object) => self.resting_offset(object, normal),
+                      None => 0.0,
+                    };
+                    surface + normal*offset
+                  })
+                } else {
+                  let plane = self.placement_plane();
+                  self.mouse_on_plane(&plane)
                 }
-                // TODO: align with goal height
-                cam_pos -= mouse_ray;
-                cam_pos.y = self.placing_height;
-              }
+              };
               
-              if let Some(object) = &mut self.object_being_placed {
-                if self.options.snap_to_grid {
-                  cam_pos.x = cam_pos.x.round();
-                  cam_pos.y = cam_pos.y.round();
-                  cam_pos.z = cam_pos.z.round();
+              if let Some(mut position) = target {
+                if let Some(object) = &mut self.object_being_placed {
+                  if self.options.snap_to_grid {
+                    position.x = position.x.round();
+                    if !self.options.surface_snap {
+                      position.y = position.y.round();
+                    }
+                    position.z = position.z.round();
+                  }
+                  object.set_position(position);
                 }
-                object.set_position(cam_pos.xyz());
               }
             }
           }
         }
             
+        // Params shown in the inspector follow the script as it is edited
+        self.script_check_timer += delta_time;
+        if let (Some(lua), true) = (&mut lua, self.script_check_timer >= scripting::SCRIPT_CHECK_INTERVAL) {
+          self.script_check_timer = 0.0;
+          let mut times = ModifiedTimes::new();
+          if let Some(object) = &mut self.object_being_placed {
+            object.refresh_script_params(lua, &mut times, &mut self.logs);
+          }
+          if self.object_selected > 1 {
+            self.world_objects[self.object_selected as usize-2].refresh_script_params(lua, &mut times, &mut self.logs);
+          }
+        }
+        
         if let Some(object) = &mut self.object_being_placed {
-          object.update(ui, &self.instanced_buffers, self.data.window_dim, delta_time, &mut self.logs);
+          let old_name = object.name();
+          object.update(ui, &self.instanced_buffers, &self.known_scripts, &self.script_templates, EulerOrder::from_index(self.options.euler_order), self.data.window_dim, delta_time, &mut self.logs);
+          if object.name() != old_name && object.has_named_script() {
+            let shared = self.world_objects.iter().any(|other| other.has_named_script() && other.name() == old_name);
+            object.rename_script(&old_name, shared, &mut self.logs);
+          }
         }
         
         if self.object_selected > 1 {
-          self.world_objects[self.object_selected as usize-2].update(ui, &self.instanced_buffers, self.data.window_dim, delta_time, &mut self.logs);
+          let idx = self.object_selected as usize-2;
+          let old_name = self.world_objects[idx].name();
+          self.world_objects[idx].update(ui, &self.instanced_buffers, &self.known_scripts, &self.script_templates, EulerOrder::from_index(self.options.euler_order), self.data.window_dim, delta_time, &mut self.logs);
+          if self.world_objects[idx].name() != old_name && self.world_objects[idx].has_named_script() {
+            let shared = self.world_objects.iter().enumerate().any(|(i, other)| {
+              i != idx && other.has_named_script() && other.name() == old_name
+            }) || self.object_being_placed.as_ref().map_or(false, |other| {
+              other.has_named_script() && other.name() == old_name
+            });
+            self.world_objects[idx].rename_script(&old_name, shared, &mut self.logs);
+          }
         }
       }
     }
@@ -1005,7 +1831,6 @@ impl Scene for EditorScreen {
       self.mouse_state = MouseState::World;
     
     }
-    self.f6_released_last_frame = !self.data.keys.f6_pressed();
     
     
     if self.logs.is_shown() {
@@ -1042,6 +1867,13 @@ impl Scene for EditorScreen {
       object.draw_hologram(draw_calls);
     }
     
+    if self.windows.array_tool && self.object_selected > 1 && !self.run_game {
+      let original = &self.world_objects[self.object_selected as usize-2];
+      for (position, orientation) in self.array_tool.copies(original.position(), original.orientation()) {
+        original.copy_to(0, position, orientation).draw_hologram(draw_calls);
+      }
+    }
+    
     if self.options.show_axis {
       let axis_position = Vector3::new(0.0, 0.0, 0.0);
       let axis_size = Vector3::new(50.0, 10.0, 10.0);
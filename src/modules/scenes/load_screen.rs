@@ -3,30 +3,209 @@ use maat_graphics::imgui::*;
 
 use crate::modules::scenes::Scene;
 use crate::modules::scenes::SceneData;
+use crate::modules::scenes::SceneTransition;
+use crate::modules::scenes::WelcomeScreen;
 use crate::modules::scenes::EditorScreen;
+use crate::modules::Logs;
+use crate::modules::import_export;
+use crate::modules::import_export::ImportedScene;
+use crate::modules::preferences::Preferences;
+use crate::modules::scene_browser;
 
 use hlua::Lua;
 
 use crate::cgmath::{Vector2, Vector4};
 
+use std::fs;
+use std::path::Path;
+
+// Shortest time the logo is shown, even when there is nothing to load
 const LOGO_TIMER: f32 = 1.5;
+// Models that have not reported a size after this long are treated as failed
+const MODEL_LOAD_TIMEOUT: f32 = 10.0;
+
+const FONT_LOCATION: &str = "./resources/Fonts/";
+const TEXTURE_LOCATION: &str = "./resources/Textures/";
+// Loaded in main before the load screen so it can draw itself
+const PRELOADED_FONT: &str = "TimesNewRoman";
+const PRELOADED_TEXTURE: &str = "Logo";
+
+enum Asset {
+  Model(String, String),
+  Texture(String, String),
+  Font(String, String, String),
+}
+
+impl Asset {
+  fn name(&self) -> String {
+    match self {
+      Asset::Model(reference, _) => "Model: ".to_owned() + reference,
+      Asset::Texture(reference, _) => "Texture: ".to_owned() + reference,
+      Asset::Font(reference, _, _) => "Font: ".to_owned() + reference,
+    }
+  }
+}
 
 pub struct LoadScreen {
   data: SceneData,
-  alpha: f32,
   logo_timer: f32,
-  first_loop: bool,
-  loop_num: u32,
+  logs: Option<Logs>,
+  preferences: Option<Preferences>,
+  assets: Vec<Asset>,
+  total_assets: usize,
+  current_asset: String,
+  waiting_for: Vec<String>,
+  wait_timer: f32,
+  imported_scene: Option<(String, ImportedScene)>,
 }
 
 impl LoadScreen {
-  pub fn new() -> LoadScreen {
+  // scripting_error is from setting up Lua in main, shown once the logs exist
+  pub fn new(scripting_error: Option<String>) -> LoadScreen {
+    let data = SceneData::new_default();
+    let mut logs = Logs::new(data.window_dim);
+    if let Some(e) = scripting_error {
+      logs.add_error("Failed to set up scripting: ".to_owned() + &e);
+    }
+    let preferences = Preferences::load(&mut logs);
+    
+    let (mut assets, imported_scene) = LoadScreen::scan_assets(&mut logs, &preferences);
+    // Popped from the back while loading
+    assets.reverse();
+    let total_assets = assets.len();
+    
     LoadScreen {
-      data: SceneData::new_default(),
-      alpha: 0.0,
+      data,
       logo_timer: LOGO_TIMER,
-      first_loop: true,
-      loop_num: 0,
+      logs: Some(logs),
+      preferences: Some(preferences),
+      assets,
+      total_assets,
+      current_asset: "".to_string(),
+      waiting_for: Vec::new(),
+      wait_timer: 0.0,
+      imported_scene,
+    }
+  }
+  
+  // The default scene is imported here to find its models and handed to the editor
+  // so it isn't read twice
+  fn scan_assets(logs: &mut Logs, preferences: &Preferences) -> (Vec<Asset>, Option<(String, ImportedScene)>) {
+    let mut assets = Vec::new();
+    let mut imported_scene = None;
+    // Comes with the editor rather than the project
+    assets.push(Asset::Model("Axis".to_string(), preferences.editor_file("Models/Axis.glb").display().to_string()));
+    
+    let known_models = import_export::get_models(logs);
+    
+    // Scenes from other projects are loaded once their project is opened
+    if let Some(recent) = preferences.default_scene.as_ref().or(preferences.last_scene()) {
+      let scene = &recent.scene;
+      if recent.project == scene_browser::current_project() && Path::new(&(scene_browser::project_file("Scenes/") + scene)).exists() {
+        let imported = import_export::import(scene.to_string(), logs);
+        for (reference, location) in &imported.0 {
+          if reference == "Axis" {
+            continue;
+          }
+          
+          if known_models.iter().any(|(name, _, _)| name == reference) {
+            assets.push(Asset::Model(reference.to_string(), location.to_string()));
+          } else {
+            logs.add_error("Scene ".to_owned() + scene + " uses missing model " + location);
+          }
+        }
+        imported_scene = Some((scene.to_string(), imported));
+      }
+    }
+    
+    match fs::read_dir(FONT_LOCATION) {
+      Ok(paths) => {
+        for path in paths {
+          if let Ok(entry) = path {
+            let path = entry.path();
+            if !path.extension().map_or(false, |extension| extension == "fnt") {
+              continue;
+            }
+            
+            let reference = path.file_stem().unwrap().to_string_lossy().to_string();
+            if reference == PRELOADED_FONT {
+              continue;
+            }
+            
+            let texture = FONT_LOCATION.to_owned() + &reference + ".png";
+            if Path::new(&texture).exists() {
+              assets.push(Asset::Font(reference, texture, path.display().to_string()));
+            } else {
+              logs.add_error("Font ".to_owned() + &reference + " has no texture " + &texture);
+            }
+          }
+        }
+      },
+      Err(e) => {
+        logs.add_error("Fonts: ".to_owned() + &e.to_string());
+      }
+    }
+    
+    match fs::read_dir(TEXTURE_LOCATION) {
+      Ok(paths) => {
+        for path in paths {
+          if let Ok(entry) = path {
+            let path = entry.path();
+            if !path.extension().map_or(false, |extension| extension == "png") {
+              continue;
+            }
+            
+            let reference = path.file_stem().unwrap().to_string_lossy().to_string();
+            if reference != PRELOADED_TEXTURE {
+              assets.push(Asset::Texture(reference, path.display().to_string()));
+            }
+          }
+        }
+      },
+      Err(e) => {
+        logs.add_error("Textures: ".to_owned() + &e.to_string());
+      }
+    }
+    
+    (assets, imported_scene)
+  }
+  
+  fn add_error(&mut self, err: String) {
+    if let Some(logs) = &mut self.logs {
+      logs.add_error(err);
+    }
+  }
+  
+  fn progress(&self) -> f32 {
+    if self.total_assets == 0 {
+      return 1.0;
+    }
+    
+    let remaining = self.assets.len() + self.waiting_for.len();
+    (self.total_assets - remaining.min(self.total_assets)) as f32 / self.total_assets as f32
+  }
+  
+  fn load_asset(&mut self, asset: Asset) {
+    self.current_asset = asset.name();
+    
+    match asset {
+      Asset::Model(reference, location) => {
+        self.waiting_for.push(reference.to_string());
+        self.data.models_to_load.push((reference, location));
+      },
+      Asset::Texture(reference, location) => {
+        self.data.textures_to_load.push((reference, location));
+      },
+      Asset::Font(reference, texture, font) => {
+        match fs::read(&font) {
+          Ok(font) => {
+            self.data.fonts_to_load.push((reference, texture, font));
+          },
+          Err(e) => {
+            self.add_error("Font ".to_owned() + &reference + ": " + &e.to_string());
+          }
+        }
+      },
     }
   }
 }
@@ -40,32 +219,64 @@ impl Scene for LoadScreen {
     &mut self.data
   }
   
-  fn future_scene(&mut self, window_size: Vector2<f32>) -> Box<Scene> {
-    Box::new(EditorScreen::new(window_size, self.data.model_sizes.clone()))
-  }
-  
   fn update(&mut self, _ui: Option<&Ui>, _lua: Option<&mut Lua>, delta_time: f32) {
     self.logo_timer -= delta_time as f32;
-    self.alpha = 1.0 - (self.logo_timer / (LOGO_TIMER*0.7));
     
-    if self.logo_timer <= 0.0 {
-      self.mut_data().next_scene = true;
+    // One asset a frame so the progress bar moves
+    if let Some(asset) = self.assets.pop() {
+      self.load_asset(asset);
+      return;
+    }
+    
+    let model_sizes = &self.data.model_sizes;
+    self.waiting_for.retain(|reference| !model_sizes.iter().any(|(loaded, _)| loaded == reference));
+    if self.waiting_for.len() > 0 {
+      self.current_asset = "Model: ".to_owned() + &self.waiting_for[0];
+      self.wait_timer += delta_time;
+      if self.wait_timer < MODEL_LOAD_TIMEOUT {
+        return;
+      }
+      
+      for reference in self.waiting_for.clone() {
+        self.add_error("Model ".to_owned() + &reference + " did not finish loading");
+      }
+      self.waiting_for.clear();
     }
     
-    if self.loop_num == 1 {
-      self.first_loop = false;
+    self.current_asset = "Done".to_string();
+    
+    if self.logo_timer > 0.0 || self.data.transition.is_some() {
+      return;
     }
-    self.loop_num += 1;
+    
+    let mut logs = self.logs.take().unwrap();
+    let mut preferences = self.preferences.take().unwrap();
+    
+    if let Some(default) = preferences.default_scene.clone() {
+      let project_open = default.project == scene_browser::current_project() ||
+                         scene_browser::open_project(&default.project, &mut preferences, &mut logs);
+      if project_open && Path::new(&(scene_browser::project_file("Scenes/") + &default.scene)).exists() {
+        let mut editor = EditorScreen::new(self.data.window_dim, self.data.model_sizes.clone(), logs, preferences);
+        match self.imported_scene.take() {
+          Some((scene, imported)) if scene == default.scene => editor.open_imported_scene(scene, imported),
+          _ => editor.open_scene(default.scene),
+        }
+        self.mut_data().transition = Some(SceneTransition::Replace(Box::new(editor)));
+        return;
+      }
+      
+      logs.add_error("Default scene ".to_owned() + &default.scene + " could not be found");
+    }
+    
+    let welcome = WelcomeScreen::new(self.data.window_dim, self.data.model_sizes.clone(), logs, preferences);
+    
+    self.mut_data().transition = Some(SceneTransition::Replace(Box::new(welcome)));
   }
   
   fn draw(&self, draw_calls: &mut Vec<DrawCall>) {
     let dim = self.data().window_dim;
     let (width, height) = (dim.x as f32, dim.y as f32);
     
-    if self.first_loop {
-      draw_calls.push(DrawCall::load_model("Axis".to_string()));
-    }
-    
     draw_calls.push(DrawCall::set_texture_scale(1.0));
     
     draw_calls.push(
@@ -90,11 +301,30 @@ impl Scene for LoadScreen {
                                 String::from("Arial"))
     );
     
+    let progress = self.progress();
+    let bar_width = width*0.6;
+    let bar_left = width*0.2;
+    
     draw_calls.push(
-        DrawCall::draw_coloured(Vector2::new(width*0.5, height*0.5),
-                                Vector2::new(width*5.0, height*5.0),
-                                Vector4::new(0.1, 0.1, 0.1, self.alpha),
-                                90.0)
+        DrawCall::draw_coloured(Vector2::new(width*0.5, height*0.2),
+                                Vector2::new(bar_width, 20.0),
+                                Vector4::new(0.3, 0.3, 0.3, 1.0),
+                                0.0)
+    );
+    
+    draw_calls.push(
+        DrawCall::draw_coloured(Vector2::new(bar_left + bar_width*progress*0.5, height*0.2),
+                                Vector2::new(bar_width*progress, 20.0),
+                                Vector4::new(0.2, 0.6, 1.0, 1.0),
+                                0.0)
+    );
+    
+    draw_calls.push(
+      DrawCall::draw_text_basic(Vector2::new(bar_left, height*0.2-48.0),
+                                Vector2::new(128.0, 128.0),
+                                Vector4::new(0.0, 0.0, 0.0, 1.0),
+                                self.current_asset.to_string(),
+                                String::from("Arial"))
     );
   }
 }
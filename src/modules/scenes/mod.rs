@@ -9,18 +9,31 @@ use hlua::Lua;
 use std::vec::Vec;
 
 use maat_graphics::winit;
+use maat_graphics::winit::{VirtualKeyCode, ModifiersState};
 use maat_graphics::winit::MouseScrollDelta::LineDelta;
 use maat_graphics::winit::MouseScrollDelta::PixelDelta;
 
+use crate::modules::keymap;
+use crate::modules::scripting::ScriptInput;
+
 use crate::cgmath::{Vector2, Vector3};
 
 pub use self::load_screen::LoadScreen;
+pub use self::welcome_screen::WelcomeScreen;
 pub use self::editor_screen::EditorScreen;
 pub use self::editor_screen::GameOptions;
 
 mod load_screen;
+mod welcome_screen;
 mod editor_screen;
 
+// How the scene stack in main changes once the current frame is done
+pub enum SceneTransition {
+  Push(Box<Scene>),
+  Pop,
+  Replace(Box<Scene>),
+}
+
 pub struct ImGuiInfo {
   wants_mouse: bool,
   wants_keyboard: bool,
@@ -28,7 +41,7 @@ pub struct ImGuiInfo {
 
 pub struct SceneData {
   pub should_close: bool,
-  pub next_scene: bool,
+  pub transition: Option<SceneTransition>,
   mouse_pos: Vector2<f32>,
   pub scroll_delta: f32,
   left_mouse: bool,
@@ -38,22 +51,29 @@ pub struct SceneData {
   pub right_mouse_dragged: bool,
   pub middle_mouse_dragged: bool,
   pub window_dim: Vector2<f32>,
+  pub ui_scale: f32,
   pub currently_pressed: Vec<u32>,
   pub released_this_render: Vec<u32>,
+  pub modifiers: ModifiersState,
+  // Keys by meaning rather than position, used by the keymap
+  pub keys_held: Vec<VirtualKeyCode>,
+  pub keys_pressed_this_frame: Vec<VirtualKeyCode>,
+  pub keys_released_this_frame: Vec<VirtualKeyCode>,
   pub keys: MappedKeys,
-  pub window_resized: bool,
   pub controller: Controller,
   pub model_sizes: Vec<(String, Vector3<f32>)>,
   imgui_info: ImGuiInfo,
   models_to_load: Vec<(String, String)>,
   models_to_unload: Vec<String>,
+  textures_to_load: Vec<(String, String)>,
+  fonts_to_load: Vec<(String, String, Vec<u8>)>,
 }
 
 impl SceneData {
   pub fn new(window_size: Vector2<f32>, model_sizes: Vec<(String, Vector3<f32>)>) -> SceneData {
     SceneData {
       should_close: false,
-      next_scene: false,
+      transition: None,
       mouse_pos: Vector2::new(0.0, 0.0),
       scroll_delta: 0.0, // Scroll Delta is either -1, 0 or 1
       left_mouse: false,
@@ -65,20 +85,26 @@ impl SceneData {
       window_dim: window_size,
       currently_pressed: Vec::new(),
       released_this_render: Vec::new(),
+      modifiers: ModifiersState::default(),
+      keys_held: Vec::new(),
+      keys_pressed_this_frame: Vec::new(),
+      keys_released_this_frame: Vec::new(),
       keys: MappedKeys::new(),
-      window_resized: false,
       controller: Controller::new(),
       model_sizes,
       imgui_info: ImGuiInfo { wants_mouse: false, wants_keyboard: false },
+      ui_scale: 1.0,
       models_to_load: Vec::new(),
       models_to_unload: Vec::new(),
+      textures_to_load: Vec::new(),
+      fonts_to_load: Vec::new(),
     }
   }
   
   pub fn new_default() -> SceneData {
     SceneData {
       should_close: false,
-      next_scene: false,
+      transition: None,
       mouse_pos: Vector2::new(0.0, 0.0),
       scroll_delta: 0.0, // Scroll Delta is either -1, 0 or 1
       left_mouse: false,
@@ -90,13 +116,19 @@ impl SceneData {
       window_dim: Vector2::new(1.0, 1.0),
       currently_pressed: Vec::new(),
       released_this_render: Vec::new(),
+      modifiers: ModifiersState::default(),
+      keys_held: Vec::new(),
+      keys_pressed_this_frame: Vec::new(),
+      keys_released_this_frame: Vec::new(),
       keys: MappedKeys::new(),
-      window_resized: false,
       controller: Controller::new(),
       model_sizes: Vec::new(),
       imgui_info: ImGuiInfo { wants_mouse: false, wants_keyboard: false },
+      ui_scale: 1.0,
       models_to_load: Vec::new(),
       models_to_unload: Vec::new(),
+      textures_to_load: Vec::new(),
+      fonts_to_load: Vec::new(),
     }
   }
   
@@ -104,11 +136,66 @@ impl SceneData {
     self.mouse_pos = mouse_position;
   }
   
-  pub fn update_window_dim(&mut self, dim: Vector2<f32>) {
+  pub fn key_held(&self, key: VirtualKeyCode) -> bool {
+    self.keys_held.contains(&key)
+  }
+  
+  pub fn key_pressed(&self, key: VirtualKeyCode) -> bool {
+    self.keys_pressed_this_frame.contains(&key)
+  }
+  
+  // What scripts can see, keyboard and mouse are left out while imgui is using them
+  pub fn script_input(&self) -> ScriptInput {
+    let mut input = ScriptInput::new();
+    
+    if !self.imgui_info.wants_keyboard {
+      let names = |keys: &Vec<VirtualKeyCode>| {
+        keys.iter().filter_map(|key| keymap::key_name(*key)).map(|name| name.to_string()).collect::<Vec<String>>()
+      };
+      input.keys_held = names(&self.keys_held);
+      input.keys_pressed = names(&self.keys_pressed_this_frame);
+      input.keys_released = names(&self.keys_released_this_frame);
+      
+      let modifiers = [("Shift", self.modifiers.shift), ("Ctrl", self.modifiers.ctrl), ("Alt", self.modifiers.alt)];
+      for (name, held) in modifiers.iter() {
+        if *held {
+          input.keys_held.push(name.to_string());
+        }
+      }
+    }
+    
+    input.mouse_position = self.mouse_pos;
+    if !self.imgui_info.wants_mouse {
+      input.mouse_buttons = [self.left_mouse, self.right_mouse, self.middle_mouse];
+      input.scroll = self.scroll_delta;
+    }
+    
+    input.left_stick = self.controller.left_stick_position();
+    input.right_stick = self.controller.right_stick_position();
+    input.left_trigger = self.controller.left_trigger_position();
+    input.right_trigger = self.controller.right_trigger_position();
+    let buttons = [("A", self.controller.a_button_pressed()),
+                   ("B", self.controller.b_button_pressed()),
+                   ("X", self.controller.x_button_pressed()),
+                   ("Y", self.controller.y_button_pressed()),
+                   ("Start", self.controller.start_button_pressed())];
+    for (name, held) in buttons.iter() {
+      if *held {
+        input.gamepad_buttons.push(name.to_string());
+      }
+    }
+    
+    input
+  }
+  
+  // Returns true if the window changed size
+  pub fn update_window_dim(&mut self, dim: Vector2<f32>) -> bool {
     if self.window_dim != dim {
-      self.window_resized = true;
       self.window_dim = dim;
+      return true;
     }
+    
+    false
   }
 }
 
@@ -116,19 +203,35 @@ impl SceneData {
 pub trait Scene {
   fn data(&self) -> &SceneData;
   fn mut_data(&mut self) -> &mut SceneData;
-  fn future_scene(&mut self, window_size: Vector2<f32>) -> Box<Scene>;
   
   fn update(&mut self, ui: Option<&Ui>, lua: Option<&mut Lua>, delta_time: f32);
   fn draw(&self, draw_calls: &mut Vec<DrawCall>);
   
-  fn scene_finished(&self) -> bool {
-    self.data().next_scene
+  // Called after the window changed size, window_dim is already updated
+  fn resized(&mut self, _window_size: Vector2<f32>) {
+    
+  }
+  
+  // Called on every scene in the stack when the editor shuts down
+  fn closing(&mut self) {
+    
+  }
+  
+  fn take_transition(&mut self) -> Option<SceneTransition> {
+    self.mut_data().transition.take()
   }
   
   fn reset_scroll_value(&mut self) {
     self.mut_data().scroll_delta = 0.0;
   }
   
+  // Called once the scene has updated, input events for the next frame come after
+  fn reset_frame_input(&mut self) {
+    self.reset_scroll_value();
+    self.mut_data().keys_pressed_this_frame.clear();
+    self.mut_data().keys_released_this_frame.clear();
+  }
+  
   fn get_models_to_load(&mut self) -> Vec<(String, String)> {
     let models = self.data().models_to_load.clone();
     self.mut_data().models_to_load = Vec::new();
@@ -136,6 +239,21 @@ pub trait Scene {
     models
   }
   
+  fn get_textures_to_load(&mut self) -> Vec<(String, String)> {
+    let textures = self.data().textures_to_load.clone();
+    self.mut_data().textures_to_load = Vec::new();
+    
+    textures
+  }
+  
+  // Reference, font texture location and the contents of the .fnt file
+  fn get_fonts_to_load(&mut self) -> Vec<(String, String, Vec<u8>)> {
+    let fonts = self.data().fonts_to_load.clone();
+    self.mut_data().fonts_to_load = Vec::new();
+    
+    fonts
+  }
+  
   fn get_models_to_unload(&mut self) -> Vec<String> {
     let mut idxs = Vec::new();
     for i in 0..self.data().models_to_unload.len() {
@@ -157,7 +275,9 @@ pub trait Scene {
   }
   
   fn set_window_dimensions(&mut self, new_dim: Vector2<f32>) {
-    self.mut_data().update_window_dim(new_dim);
+    if self.mut_data().update_window_dim(new_dim) {
+      self.resized(new_dim);
+    }
   }
   
   fn set_mouse_position(&mut self, mouse_position: Vector2<f32>) {
@@ -213,6 +333,20 @@ pub trait Scene {
       },
       winit::WindowEvent::KeyboardInput{device_id: _, input} => {
         let key = input.scancode;
+        self.mut_data().modifiers = input.modifiers;
+        
+        if let Some(virtual_key) = input.virtual_keycode {
+          if input.state == winit::ElementState::Pressed {
+            // Held keys repeat, only the first press counts
+            if !self.data().key_held(virtual_key) {
+              self.mut_data().keys_held.push(virtual_key);
+              self.mut_data().keys_pressed_this_frame.push(virtual_key);
+            }
+          } else {
+            self.mut_data().keys_held.retain(|held| *held != virtual_key);
+            self.mut_data().keys_released_this_frame.push(virtual_key);
+          }
+        }
         
         if input.state == winit::ElementState::Pressed {
           let mut already_pressed = false;
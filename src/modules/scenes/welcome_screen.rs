@@ -0,0 +1,134 @@
+use maat_graphics::DrawCall;
+use maat_graphics::imgui::*;
+
+use crate::modules::scenes::Scene;
+use crate::modules::scenes::SceneData;
+use crate::modules::scenes::SceneTransition;
+use crate::modules::scenes::EditorScreen;
+use crate::modules::Logs;
+use crate::modules::preferences::Preferences;
+use crate::modules::scene_browser;
+use crate::modules::scene_browser::{SceneBrowser, BrowserAction};
+
+use hlua::Lua;
+
+use crate::cgmath::{Vector2, Vector3, Vector4};
+
+pub struct WelcomeScreen {
+  data: SceneData,
+  logs: Option<Logs>,
+  preferences: Option<Preferences>,
+  browser: SceneBrowser,
+}
+
+impl WelcomeScreen {
+  pub fn new(window_size: Vector2<f32>, model_sizes: Vec<(String, Vector3<f32>)>, logs: Logs, preferences: Preferences) -> WelcomeScreen {
+    let mut logs = logs;
+    let mut browser = SceneBrowser::new(false);
+    browser.refresh(&preferences, &mut logs);
+
+    WelcomeScreen {
+      data: SceneData::new(window_size, model_sizes),
+      logs: Some(logs),
+      preferences: Some(preferences),
+      browser,
+    }
+  }
+
+  fn open_editor(&mut self) -> EditorScreen {
+    let logs = self.logs.take().unwrap();
+    let preferences = self.preferences.take().unwrap();
+
+    EditorScreen::new(self.data.window_dim, self.data.model_sizes.clone(), logs, preferences)
+  }
+
+  fn open_project(&mut self, project: &str) -> bool {
+    if project == scene_browser::current_project() {
+      return true;
+    }
+
+    match (&mut self.preferences, &mut self.logs) {
+      (Some(preferences), Some(logs)) => scene_browser::open_project(project, preferences, logs),
+      _ => false,
+    }
+  }
+}
+
+impl Scene for WelcomeScreen {
+  fn data(&self) -> &SceneData {
+    &self.data
+  }
+
+  fn mut_data(&mut self) -> &mut SceneData {
+    &mut self.data
+  }
+
+  fn resized(&mut self, window_size: Vector2<f32>) {
+    if let Some(logs) = &mut self.logs {
+      logs.set_window_size(window_size);
+    }
+  }
+
+  fn update(&mut self, ui: Option<&Ui>, _lua: Option<&mut Lua>, _delta_time: f32) {
+    // Already handed over to the editor
+    if self.data.transition.is_some() {
+      return;
+    }
+
+    if let Some(ui) = &ui {
+      self.mut_data().imgui_info.wants_mouse = ui.want_capture_mouse();
+      self.mut_data().imgui_info.wants_keyboard = ui.want_capture_keyboard();
+    }
+
+    match self.browser.update(ui, self.data.window_dim) {
+      Some(BrowserAction::New(name)) => {
+        let mut editor = self.open_editor();
+        editor.new_scene(name);
+        self.data.transition = Some(SceneTransition::Replace(Box::new(editor)));
+        return;
+      },
+      Some(BrowserAction::Open(project, scene)) => {
+        if self.open_project(&project) {
+          let mut editor = self.open_editor();
+          editor.open_scene(scene);
+          self.data.transition = Some(SceneTransition::Replace(Box::new(editor)));
+          return;
+        }
+      },
+      Some(BrowserAction::OpenFolder(project)) => {
+        if self.open_project(&project) {
+          if let (Some(preferences), Some(logs)) = (&self.preferences, &mut self.logs) {
+            self.browser.refresh(preferences, logs);
+          }
+        }
+      },
+      Some(BrowserAction::Cancel) | None => {},
+    }
+
+    if let Some(logs) = &mut self.logs {
+      if logs.is_shown() {
+        logs.draw(ui);
+      }
+    }
+  }
+
+  fn draw(&self, draw_calls: &mut Vec<DrawCall>) {
+    let dim = self.data().window_dim;
+    let (width, height) = (dim.x as f32, dim.y as f32);
+
+    draw_calls.push(
+      DrawCall::draw_textured(Vector2::new(width-150.0, 150.0),
+                              Vector2::new(250.0, 250.0),
+                              0.0,
+                              String::from("Logo"))
+    );
+
+    draw_calls.push(
+      DrawCall::draw_text_basic(Vector2::new(32.0, height-48.0),
+                                Vector2::new(128.0, 128.0),
+                                Vector4::new(1.0, 1.0, 1.0, 1.0),
+                                String::from("Maat Editor"),
+                                String::from("Arial"))
+    );
+  }
+}
@@ -0,0 +1,158 @@
+use maat_graphics::imgui::*;
+
+use crate::cgmath::Vector2;
+
+pub enum ScriptErrorAction {
+  Select(u32),
+  Clear,
+}
+
+pub struct ScriptError {
+  // None for the scene script
+  object_id: Option<u32>,
+  object_name: String,
+  script: String,
+  line: Option<u32>,
+  message: String,
+  count: u32,
+}
+
+impl ScriptError {
+  pub fn describe(&self) -> String {
+    let location = match self.line {
+      Some(line) => self.script.to_owned() + ":" + &line.to_string(),
+      None => self.script.to_string(),
+    };
+
+    self.object_name.to_owned() + " (" + &location + "): " + &self.message
+  }
+}
+
+// Lua puts "<chunk>:<line>: " in front of the message, chunks are script paths
+fn split_line(message: &str) -> (Option<u32>, String) {
+  if let Some(idx) = message.find(".lua:") {
+    let rest = &message[idx+5..];
+    if let Some(end) = rest.find(':') {
+      if let Ok(line) = rest[..end].parse::<u32>() {
+        return (Some(line), rest[end+1..].trim().to_string());
+      }
+    }
+  }
+
+  (None, message.trim().to_string())
+}
+
+// Errors from scripts while running, the same error is counted rather than repeated
+pub struct ScriptErrors {
+  errors: Vec<ScriptError>,
+  pub pause_on_error: bool,
+  // Errors not seen before, waiting to go into log.ini
+  new_errors: Vec<String>,
+  // Set by the first error since pause_on_error was last acted on, with the object that caused it
+  pause_for: Option<Option<u32>>,
+}
+
+impl ScriptErrors {
+  pub fn new() -> ScriptErrors {
+    ScriptErrors {
+      errors: Vec::new(),
+      pause_on_error: false,
+      new_errors: Vec::new(),
+      pause_for: None,
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.errors.clear();
+    self.new_errors.clear();
+    self.pause_for = None;
+  }
+
+  pub fn add(&mut self, object_id: u32, object_name: &str, script: &str, message: String) {
+    self.push(Some(object_id), object_name, script, message, true);
+  }
+
+  pub fn add_scene(&mut self, script: &str, message: String) {
+    self.push(None, "Scene", script, message, true);
+  }
+
+  // Listed like an error but never pauses the game
+  pub fn warn(&mut self, object_id: u32, object_name: &str, script: &str, message: String) {
+    self.push(Some(object_id), object_name, script, "Warning: ".to_owned() + &message, false);
+  }
+
+  fn push(&mut self, object_id: Option<u32>, object_name: &str, script: &str, message: String, can_pause: bool) {
+    let (line, message) = split_line(&message);
+
+    if let Some(error) = self.errors.iter_mut().find(|error| {
+      error.object_id == object_id && error.line == line && error.message == message
+    }) {
+      error.count += 1;
+      return;
+    }
+
+    let error = ScriptError {
+      object_id,
+      object_name: object_name.to_string(),
+      script: script.to_string(),
+      line,
+      message,
+      count: 1,
+    };
+
+    self.new_errors.push(error.describe());
+    if can_pause && self.pause_on_error && self.pause_for.is_none() {
+      self.pause_for = Some(object_id);
+    }
+    self.errors.push(error);
+  }
+
+  // Descriptions of errors added since the last call
+  pub fn take_new_errors(&mut self) -> Vec<String> {
+    self.new_errors.drain(..).collect()
+  }
+
+  // Some when the game should pause, holding the object to select if there is one
+  pub fn take_pause(&mut self) -> Option<Option<u32>> {
+    self.pause_for.take()
+  }
+
+  pub fn update(&mut self, ui: Option<&Ui>, selected_id: Option<u32>, window_dim: Vector2<f32>) -> Option<ScriptErrorAction> {
+    let mut action = None;
+
+    if let Some(ui) = &ui {
+      let errors = &self.errors;
+      let pause_on_error = &mut self.pause_on_error;
+
+      ui.window(im_str!("Script Errors"))
+        .size([500.0, 200.0], Condition::FirstUseEver)
+        .position([220.0, window_dim.y - 450.0], Condition::FirstUseEver)
+        .build(|| {
+          ui.checkbox(im_str!("Pause on error"), pause_on_error);
+          ui.same_line(0.0);
+          if ui.button(im_str!("Clear"), [0.0, 0.0]) {
+            action = Some(ScriptErrorAction::Clear);
+          }
+          ui.separator();
+
+          if errors.len() == 0 {
+            ui.text(im_str!("No errors"));
+          }
+
+          for i in 0..errors.len() {
+            let error = &errors[i];
+            let marker = if error.object_id.is_some() && selected_id == error.object_id { "> " } else { "" };
+            if let Some(object_id) = error.object_id {
+              if ui.button(&im_str!("Select##script_error{}", i), [0.0, 0.0]) {
+                action = Some(ScriptErrorAction::Select(object_id));
+              }
+              ui.same_line(0.0);
+            }
+            ui.text_wrapped(&im_str!("{}{} x{}", marker, error.describe(), error.count));
+          }
+        });
+    }
+
+    action
+  }
+}
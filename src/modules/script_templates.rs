@@ -0,0 +1,206 @@
+use crate::modules::Logs;
+use crate::modules::scene_browser::project_file;
+
+use std::fs;
+use std::path::Path;
+
+// Templates are read from here, a team can add its own .lua files next to the built in ones
+pub const TEMPLATE_FOLDER: &str = "Templates";
+
+// Replaced with the object's name, made safe to use in a Lua name
+pub const NAME_PLACEHOLDER: &str = "{{name}}";
+
+// Written to the template folder when it doesn't exist yet
+const BUILT_IN: [(&str, &str); 6] = [
+("empty", "-- Called once when run mode starts
+function init()
+end
+
+-- Called every frame, dt is the time since the last frame in seconds
+function update(dt)
+end
+"),
+("physics_integrator", "local function {{name}}_integrate(vector, change, dt)
+  vector.x = vector.x + change.x*dt
+  vector.y = vector.y + change.y*dt
+  vector.z = vector.z + change.z*dt
+end
+
+function init()
+end
+
+-- Called 60 times a second whatever the frame rate
+function fixed_update(dt)
+  {{name}}_integrate(self.velocity, self.acceleration, dt)
+  {{name}}_integrate(self.position, self.velocity, dt)
+end
+"),
+("rotator", "-- Degrees a second around each axis
+params = { speed_x = 0.0, speed_y = 45.0, speed_z = 0.0 }
+
+local function {{name}}_wrap(degrees)
+  return degrees % 360
+end
+
+function update(dt)
+  self.rotation.x = {{name}}_wrap(self.rotation.x + params.speed_x*dt)
+  self.rotation.y = {{name}}_wrap(self.rotation.y + params.speed_y*dt)
+  self.rotation.z = {{name}}_wrap(self.rotation.z + params.speed_z*dt)
+end
+"),
+("patrol", "-- waypoints are the names of objects to walk between, separated by commas
+params = { waypoints = \"\", speed = 2.0 }
+
+local points = {}
+local current = 1
+
+local function {{name}}_find_points()
+  points = {}
+  for name in string.gmatch(params.waypoints, \"[^,%s]+\") do
+    local waypoint = scene.find_by_name(name)
+    if waypoint ~= nil then
+      points[#points + 1] = waypoint
+    end
+  end
+end
+
+function init()
+  {{name}}_find_points()
+end
+
+function update(dt)
+  local target = points[current]
+  if target == nil then
+    return
+  end
+
+  local dx = target.position.x - self.position.x
+  local dy = target.position.y - self.position.y
+  local dz = target.position.z - self.position.z
+  local distance = math.sqrt(dx*dx + dy*dy + dz*dz)
+  local step = params.speed*dt
+
+  if distance <= step then
+    self.position.x = target.position.x
+    self.position.y = target.position.y
+    self.position.z = target.position.z
+    current = current % #points + 1
+  else
+    self.position.x = self.position.x + dx/distance*step
+    self.position.y = self.position.y + dy/distance*step
+    self.position.z = self.position.z + dz/distance*step
+  end
+end
+"),
+("follow_target", "-- target is the name of the object to follow, it stops at distance from it
+params = { target = \"\", speed = 3.0, distance = 2.0 }
+
+local function {{name}}_target()
+  return scene.find_by_name(params.target)
+end
+
+function update(dt)
+  local target = {{name}}_target()
+  if target == nil then
+    return
+  end
+
+  local dx = target.position.x - self.position.x
+  local dy = target.position.y - self.position.y
+  local dz = target.position.z - self.position.z
+  local length = math.sqrt(dx*dx + dy*dy + dz*dz)
+  if length <= params.distance then
+    return
+  end
+
+  local step = math.min(params.speed*dt, length - params.distance)
+  self.position.x = self.position.x + dx/length*step
+  self.position.y = self.position.y + dy/length*step
+  self.position.z = self.position.z + dz/length*step
+end
+"),
+("keyboard_mover", "-- Moves on x and z with W, A, S and D or the left stick, hold Shift to go faster
+params = { speed = 5.0, sprint = 2.0 }
+
+local function {{name}}_direction()
+  local x, z = input.gamepad_stick(\"left\")
+  z = -z
+  if input.key_held(\"W\") then z = z - 1 end
+  if input.key_held(\"S\") then z = z + 1 end
+  if input.key_held(\"A\") then x = x - 1 end
+  if input.key_held(\"D\") then x = x + 1 end
+  return x, z
+end
+
+function update(dt)
+  local x, z = {{name}}_direction()
+  local speed = params.speed
+  if input.key_held(\"Shift\") then
+    speed = speed*params.sprint
+  end
+
+  self.position.x = self.position.x + x*speed*dt
+  self.position.z = self.position.z + z*speed*dt
+end
+"),
+];
+
+pub struct ScriptTemplate {
+  pub name: String,
+  source: String,
+}
+
+impl ScriptTemplate {
+  // The template's source with every placeholder replaced by the object's name
+  pub fn source_for(&self, object_name: &str) -> String {
+    let mut lua_name = object_name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect::<String>();
+    if lua_name.starts_with(|c: char| c.is_ascii_digit()) {
+      lua_name.insert(0, '_');
+    }
+    self.source.replace(NAME_PLACEHOLDER, &lua_name)
+  }
+}
+
+// Every .lua file in the template folder, named by the file name without .lua
+pub fn load_templates(logs: &mut Logs) -> Vec<ScriptTemplate> {
+  let folder = project_file(TEMPLATE_FOLDER);
+  if !Path::new(&folder).exists() {
+    if let Err(e) = fs::create_dir_all(&folder) {
+      logs.add_error(e.to_string());
+    }
+
+    for (name, source) in BUILT_IN.iter() {
+      if let Err(e) = fs::write(Path::new(&folder).join(name.to_string() + ".lua"), source) {
+        logs.add_error("Templates: ".to_owned() + &e.to_string());
+      }
+    }
+  }
+
+  let mut templates = Vec::new();
+
+  match fs::read_dir(&folder) {
+    Ok(paths) => {
+      for path in paths {
+        if let Ok(entry) = path {
+          let path = entry.path();
+          if path.extension().map(|extension| extension != "lua").unwrap_or(true) {
+            continue;
+          }
+
+          let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or("".to_string());
+          match fs::read_to_string(&path) {
+            Ok(source) => templates.push(ScriptTemplate { name, source }),
+            Err(e) => logs.add_error("Templates: ".to_owned() + &name + ": " + &e.to_string()),
+          }
+        }
+      }
+    },
+    Err(e) => {
+      logs.add_error("Templates: ".to_owned() + &e.to_string());
+    }
+  }
+
+  // The empty template first so it is the default
+  templates.sort_by_key(|template| (template.name != "empty", template.name.to_string()));
+  templates
+}
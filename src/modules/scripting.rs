@@ -0,0 +1,994 @@
+use crate::modules::Logs;
+use crate::modules::keymap;
+use crate::modules::scene_browser::project_file;
+use crate::modules::scene_script::{SceneVariable, VariableValue};
+
+use hlua;
+use hlua::Lua;
+
+use std::fs;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use crate::cgmath::{Vector2, Vector3};
+
+// While running Lua owns a table for every object in the scene, the editor writes
+// them before the callbacks and reads them back after, through __maat_ globals.
+// Every script runs in its own environment so scripts can't overwrite each others globals,
+// only the callbacks found when it was compiled are kept. The scene script is kept
+// under the id "scene" and has no self.
+const PRELUDE: &str = r#"
+local vectors = { "position", "rotation", "size", "velocity", "acceleration" }
+local axes = { "x", "y", "z" }
+local objects = {}
+local scripts = {}
+local environments = {}
+local spawned = {}
+local destroyed = {}
+local next_id = 0
+
+local default_path = package.path
+local library_path = ""
+
+function __maat_reset()
+  -- Modules from Scripts are loaded again each run so changes to them are picked up
+  for name in pairs(package.loaded) do
+    if package.searchpath(name, library_path) ~= nil then
+      package.loaded[name] = nil
+    end
+  end
+
+  -- require finds modules in the open project's Scripts folder first
+  library_path = __maat_scripts .. "/?.lua;" .. __maat_scripts .. "/?/init.lua"
+  package.path = library_path .. ";" .. default_path
+
+  objects = {}
+  scripts = {}
+  environments = {}
+  spawned = {}
+  destroyed = {}
+  next_id = __maat_next_id
+  scene.variables = {}
+  return ""
+end
+
+function __maat_set_variable()
+  scene.variables[__maat_variable_name] = __maat_variable_value
+  return ""
+end
+
+-- The table is kept between frames so scripts can hold on to it
+function __maat_write()
+  local id = __maat_id
+  local object = objects[id]
+  if object == nil then
+    object = {}
+    objects[id] = object
+  end
+
+  object.id = id
+  object.name = __maat_name
+  object.model = __maat_model
+  for _, vector in ipairs(vectors) do
+    local values = object[vector]
+    if type(values) ~= "table" then
+      values = {}
+      object[vector] = values
+    end
+    for _, axis in ipairs(axes) do
+      values[axis] = _G["__maat_" .. vector .. "_" .. axis]
+    end
+  end
+  return ""
+end
+
+local function read(object)
+  local errors = {}
+  for _, vector in ipairs(vectors) do
+    local values = object[vector]
+    if type(values) ~= "table" then
+      errors[#errors + 1] = object.name .. "." .. vector .. " should be a table, got " .. type(values)
+    else
+      for _, axis in ipairs(axes) do
+        local value = values[axis]
+        if type(value) == "number" then
+          _G["__maat_" .. vector .. "_" .. axis] = value
+        else
+          errors[#errors + 1] = object.name .. "." .. vector .. "." .. axis .. " should be a number, got " .. type(value)
+        end
+      end
+    end
+  end
+
+  __maat_errors = table.concat(errors, "\n")
+end
+
+-- Values with the wrong type are reported and the globals keep what the editor wrote
+function __maat_read()
+  __maat_errors = ""
+  local object = objects[__maat_id]
+  if object ~= nil then
+    read(object)
+  end
+  return ""
+end
+
+function __maat_remove()
+  objects[__maat_id] = nil
+  scripts[__maat_id] = nil
+  environments[__maat_id] = nil
+  return ""
+end
+
+-- Globals scripts moved objects with before they had self, x is self.position.x,
+-- rot_x is self.rotation.x, vel_x is self.velocity.x and so on
+local legacy_fields = {}
+for _, prefixed in ipairs({ { "", "position" }, { "rot_", "rotation" }, { "size_", "size" },
+                            { "vel_", "velocity" }, { "acc_", "acceleration" } }) do
+  for _, axis in ipairs(axes) do
+    legacy_fields[prefixed[1] .. axis] = { prefixed[2], axis }
+  end
+end
+
+-- The legacy globals read and write self so old scripts keep moving their object,
+-- anything else falls through to the shared globals
+local function environment(object)
+  local function legacy_values(env, key)
+    local field = legacy_fields[key]
+    local self = rawget(env, "self")
+    if field ~= nil and type(self) == "table" and type(self[field[1]]) == "table" then
+      return self[field[1]], field[2]
+    end
+    return nil, nil
+  end
+
+  return setmetatable({ self = object }, {
+    __index = function(env, key)
+      local values, axis = legacy_values(env, key)
+      if values ~= nil then
+        return values[axis]
+      end
+      if key == "ref_num" and type(rawget(env, "self")) == "table" then
+        return rawget(env, "self").id
+      end
+      return _G[key]
+    end,
+    __newindex = function(env, key, value)
+      local values, axis = legacy_values(env, key)
+      if values ~= nil then
+        values[axis] = value
+      else
+        rawset(env, key, value)
+      end
+    end,
+  })
+end
+
+-- Runs the script once and keeps the callbacks it defines in scripts,
+-- __maat_callbacks is a comma separated list of the names to look for.
+-- Scripts written before the callbacks existed named them after the object,
+-- <__maat_legacy_prefix>update and init are used when update and init are missing
+-- and __maat_legacy lists the ones that were.
+-- Reloading reuses the environment so globals the script set are kept,
+-- if it fails the callbacks from before keep running.
+function __maat_compile()
+  __maat_defined = ""
+  __maat_legacy = ""
+
+  local id = __maat_id
+  local env = environments[id]
+  if env == nil or not __maat_reload then
+    env = environment(objects[id])
+  end
+
+  local chunk, err = load(__maat_source, "@" .. __maat_chunk, "t", env)
+  if chunk == nil then
+    return tostring(err)
+  end
+
+  local ok, err = pcall(chunk)
+  if not ok then
+    return tostring(err)
+  end
+
+  local callbacks = {}
+  local defined = {}
+  for name in string.gmatch(__maat_callbacks, "[^,]+") do
+    local callback = rawget(env, name)
+    if type(callback) == "function" then
+      callbacks[name] = callback
+      defined[#defined + 1] = name
+    end
+  end
+
+  local legacy = {}
+  if __maat_legacy_prefix ~= "" then
+    for _, name in ipairs({ "init", "update" }) do
+      local callback = rawget(env, __maat_legacy_prefix .. name)
+      if callbacks[name] == nil and type(callback) == "function" then
+        callbacks[name] = callback
+        defined[#defined + 1] = name
+        legacy[#legacy + 1] = __maat_legacy_prefix .. name
+      end
+    end
+  end
+  __maat_defined = table.concat(defined, ",")
+  __maat_legacy = table.concat(legacy, ",")
+  scripts[id] = callbacks
+  environments[id] = env
+
+  return ""
+end
+
+-- Values from the inspector replace the script's own defaults in its params table
+function __maat_set_param()
+  local env = environments[__maat_id]
+  if env == nil then
+    return ""
+  end
+
+  local params = rawget(env, "params")
+  if type(params) ~= "table" then
+    params = {}
+    env.params = params
+  end
+  params[__maat_param_name] = __maat_param_value
+  return ""
+end
+
+-- Only these are visible while reading params, the script isn't running yet so it
+-- gets no scene, input or require and can't catch the instruction limit with pcall
+local params_globals = { "assert", "error", "ipairs", "next", "pairs", "select", "tonumber",
+                         "tostring", "type", "unpack", "getmetatable", "setmetatable",
+                         "rawequal", "rawget", "rawlen", "rawset" }
+local params_libraries = { "math", "string", "table" }
+
+local function params_environment()
+  local env = { self = {} }
+  for _, name in ipairs(params_globals) do
+    env[name] = _G[name]
+  end
+  for _, name in ipairs(params_libraries) do
+    local library = {}
+    for key, value in pairs(_G[name]) do
+      library[key] = value
+    end
+    env[name] = library
+  end
+  return env
+end
+
+-- Runs a script on its own to find the params it declares, used while editing.
+-- __maat_param_list gets a "name\ttype\tvalue" line for each number, bool or string.
+-- The script is stopped after __maat_params_limit instructions so a loop can't hang the editor.
+function __maat_params()
+  __maat_param_list = ""
+
+  local env = params_environment()
+  local chunk, err = load(__maat_source, "@" .. __maat_chunk, "t", env)
+  if chunk == nil then
+    return tostring(err)
+  end
+
+  debug.sethook(function()
+    error("took too long to declare its params", 2)
+  end, "", __maat_params_limit)
+  local ok, err = pcall(chunk)
+  debug.sethook()
+  if not ok then
+    return tostring(err)
+  end
+
+  local params = rawget(env, "params")
+  if type(params) ~= "table" then
+    return ""
+  end
+
+  local names = {}
+  for name, value in pairs(params) do
+    local kind = type(value)
+    if type(name) == "string" and (kind == "number" or kind == "boolean" or kind == "string") then
+      names[#names + 1] = name
+    end
+  end
+  table.sort(names)
+
+  local lines = {}
+  for _, name in ipairs(names) do
+    local value = params[name]
+    local kind = type(value)
+    if kind == "boolean" then
+      kind = "bool"
+    end
+    lines[#lines + 1] = name .. "\t" .. kind .. "\t" .. tostring(value)
+  end
+  __maat_param_list = table.concat(lines, "\n")
+  return ""
+end
+
+function __maat_call()
+  local callbacks = scripts[__maat_id]
+  local callback = callbacks and callbacks[__maat_callback]
+  if callback == nil then
+    return ""
+  end
+
+  local ok, err
+  if __maat_pass_delta_time then
+    ok, err = pcall(callback, __maat_delta_time)
+  else
+    ok, err = pcall(callback)
+  end
+  if not ok then
+    return tostring(err)
+  end
+  return ""
+end
+
+-- Hands spawned objects to the editor one at a time, __maat_found is false once they have all been taken
+function __maat_next_spawned()
+  __maat_found = false
+  while #spawned > 0 do
+    local object = objects[table.remove(spawned, 1)]
+    -- Destroyed in the same frame it was spawned
+    if object ~= nil then
+      __maat_found = true
+      __maat_id = object.id
+      __maat_name = object.name
+      __maat_model = object.model
+      read(object)
+      return ""
+    end
+  end
+  return ""
+end
+
+function __maat_next_destroyed()
+  __maat_found = #destroyed > 0
+  if __maat_found then
+    __maat_id = table.remove(destroyed, 1)
+  end
+  return ""
+end
+
+-- Names are compared in lower case, lists from the editor are comma separated
+local function name_set(list)
+  local set = {}
+  for name in string.gmatch(list, "[^,]+") do
+    set[string.lower(name)] = true
+  end
+  return set
+end
+
+local key_names = name_set(__maat_key_names)
+local mouse_names = name_set(__maat_mouse_names)
+local button_names = name_set(__maat_button_names)
+local keys_held = {}
+local keys_pressed = {}
+local keys_released = {}
+local mouse = {}
+local mouse_last = {}
+local buttons = {}
+local buttons_last = {}
+
+function __maat_input()
+  keys_held = name_set(__maat_keys_held)
+  keys_pressed = name_set(__maat_keys_pressed)
+  keys_released = name_set(__maat_keys_released)
+  mouse_last = mouse
+  mouse = name_set(__maat_mouse_held)
+  buttons_last = buttons
+  buttons = name_set(__maat_buttons_held)
+  return ""
+end
+
+local function check_name(names, kind, name)
+  if type(name) ~= "string" then
+    error("input: " .. kind .. " should be a string, got " .. type(name), 3)
+  end
+  name = string.lower(name)
+  if not names[name] then
+    error("input: unknown " .. kind .. " " .. name, 3)
+  end
+  return name
+end
+
+input = {}
+
+-- Key names are the ones used in keymap.ini, plus Shift, Ctrl and Alt which can only be held
+function input.key_held(key)
+  return keys_held[check_name(key_names, "key", key)] == true
+end
+
+function input.key_pressed(key)
+  return keys_pressed[check_name(key_names, "key", key)] == true
+end
+
+function input.key_released(key)
+  return keys_released[check_name(key_names, "key", key)] == true
+end
+
+-- "left", "right" or "middle"
+function input.mouse_held(button)
+  return mouse[check_name(mouse_names, "mouse button", button)] == true
+end
+
+function input.mouse_pressed(button)
+  button = check_name(mouse_names, "mouse button", button)
+  return mouse[button] == true and mouse_last[button] ~= true
+end
+
+function input.mouse_released(button)
+  button = check_name(mouse_names, "mouse button", button)
+  return mouse[button] ~= true and mouse_last[button] == true
+end
+
+function input.mouse_position()
+  return __maat_mouse_x, __maat_mouse_y
+end
+
+-- -1, 0 or 1
+function input.scroll()
+  return __maat_scroll
+end
+
+-- "left" or "right", x and y between -1 and 1
+function input.gamepad_stick(side)
+  if side == "right" then
+    return __maat_right_stick_x, __maat_right_stick_y
+  end
+  return __maat_left_stick_x, __maat_left_stick_y
+end
+
+function input.gamepad_trigger(side)
+  if side == "right" then
+    return __maat_right_trigger
+  end
+  return __maat_left_trigger
+end
+
+-- "A", "B", "X", "Y" or "Start"
+function input.gamepad_held(button)
+  return buttons[check_name(button_names, "gamepad button", button)] == true
+end
+
+function input.gamepad_pressed(button)
+  button = check_name(button_names, "gamepad button", button)
+  return buttons[button] == true and buttons_last[button] ~= true
+end
+
+function input.gamepad_released(button)
+  button = check_name(button_names, "gamepad button", button)
+  return buttons[button] ~= true and buttons_last[button] == true
+end
+
+local function vector(values, default, name)
+  if values == nil then
+    values = {}
+  elseif type(values) ~= "table" then
+    error("scene.spawn: " .. name .. " should be a table, got " .. type(values), 3)
+  end
+  return { x = values.x or default, y = values.y or default, z = values.z or default }
+end
+
+scene = { variables = {} }
+
+function scene.find_by_id(id)
+  return objects[id]
+end
+
+-- The first object with the name, nil if there isn't one
+function scene.find_by_name(name)
+  for _, object in pairs(objects) do
+    if object.name == name then
+      return object
+    end
+  end
+  return nil
+end
+
+-- for object in scene.objects() do ... end, ordered by id
+function scene.objects()
+  local ids = {}
+  for id in pairs(objects) do
+    ids[#ids + 1] = id
+  end
+  table.sort(ids)
+
+  local i = 0
+  return function()
+    i = i + 1
+    if ids[i] ~= nil then
+      return objects[ids[i]]
+    end
+  end
+end
+
+-- position, rotation and size are tables of x, y and z, anything missing uses the default
+function scene.spawn(model, position, rotation, size)
+  if type(model) ~= "string" then
+    error("scene.spawn: model should be a string, got " .. type(model), 2)
+  end
+
+  local id = next_id
+  next_id = next_id + 1
+
+  local object = {
+    id = id,
+    name = model .. id,
+    model = model,
+    position = vector(position, 0, "position"),
+    rotation = vector(rotation, 0, "rotation"),
+    size = vector(size, 1, "size"),
+    velocity = vector(nil, 0),
+    acceleration = vector(nil, 0),
+  }
+  objects[id] = object
+  spawned[#spawned + 1] = id
+  return object
+end
+
+-- Takes an object or its id, returns false if it was already gone
+function scene.destroy(object)
+  local id = object
+  if type(object) == "table" then
+    id = object.id
+  end
+
+  if id == nil or objects[id] == nil then
+    return false
+  end
+
+  objects[id] = nil
+  destroyed[#destroyed + 1] = id
+  return true
+end
+"#;
+
+// Key of the scene script in the prelude's tables, objects use their id
+const SCENE_SCRIPT_ID: &str = "scene";
+
+// Held only, scripts get these on top of the keymap's key names
+const MODIFIER_NAMES: [&str; 3] = ["Shift", "Ctrl", "Alt"];
+const MOUSE_BUTTONS: [&str; 3] = ["left", "right", "middle"];
+const GAMEPAD_BUTTONS: [&str; 5] = ["A", "B", "X", "Y", "Start"];
+
+// Run with a fixed step so physics doesn't depend on the frame rate,
+// slow frames catch up with at most MAX_FIXED_STEPS
+pub const FIXED_TIME_STEP: f32 = 1.0/60.0;
+pub const MAX_FIXED_STEPS: u32 = 5;
+
+// Reading a script's params gives up after this many Lua instructions
+const PARAMS_INSTRUCTION_LIMIT: u32 = 1000000;
+
+// Script files are checked for changes this often instead of every frame
+pub const SCRIPT_CHECK_INTERVAL: f32 = 0.5;
+
+// When script files were last modified, each file is only looked up once
+// no matter how many objects share it
+pub struct ModifiedTimes {
+  times: HashMap<String, Option<SystemTime>>,
+}
+
+impl ModifiedTimes {
+  pub fn new() -> ModifiedTimes {
+    ModifiedTimes {
+      times: HashMap::new(),
+    }
+  }
+
+  pub fn get(&mut self, file: &str) -> Option<SystemTime> {
+    *self.times.entry(file.to_string()).or_insert_with(|| {
+      fs::metadata(file).and_then(|metadata| metadata.modified()).ok()
+    })
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Callback {
+  Init,
+  Update,
+  FixedUpdate,
+  Destroy,
+}
+
+pub const CALLBACKS: [Callback; 4] = [Callback::Init, Callback::Update, Callback::FixedUpdate, Callback::Destroy];
+
+impl Callback {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Callback::Init => "init",
+      Callback::Update => "update",
+      Callback::FixedUpdate => "fixed_update",
+      Callback::Destroy => "on_destroy",
+    }
+  }
+
+  fn from_name(name: &str) -> Option<Callback> {
+    CALLBACKS.iter().find(|callback| callback.name() == name).map(|callback| *callback)
+  }
+
+  // update(dt) and fixed_update(dt)
+  fn takes_delta_time(&self) -> bool {
+    *self == Callback::Update || *self == Callback::FixedUpdate
+  }
+}
+
+// What a script sees as self, rotation is in degrees using rotation::RENDER_ORDER
+pub struct ScriptObject {
+  pub id: u32,
+  pub name: String,
+  pub model: String,
+  pub position: Vector3<f32>,
+  pub rotation: Vector3<f32>,
+  pub size: Vector3<f32>,
+  pub velocity: Vector3<f32>,
+  pub acceleration: Vector3<f32>,
+}
+
+impl ScriptObject {
+  // Global names match the ones built by the prelude
+  fn fields(&mut self) -> Vec<(String, &mut f32)> {
+    let mut fields = Vec::new();
+
+    let vectors = vec!(("position", &mut self.position),
+                       ("rotation", &mut self.rotation),
+                       ("size", &mut self.size),
+                       ("velocity", &mut self.velocity),
+                       ("acceleration", &mut self.acceleration));
+    for (name, vector) in vectors {
+      let Vector3 { x, y, z } = vector;
+      fields.push(("__maat_".to_owned() + name + "_x", x));
+      fields.push(("__maat_".to_owned() + name + "_y", y));
+      fields.push(("__maat_".to_owned() + name + "_z", z));
+    }
+
+    fields
+  }
+
+  fn read_fields(&mut self, lua: &mut Lua) -> Vec<String> {
+    for (key, value) in self.fields() {
+      if let Some(new_value) = lua.get::<f32, _>(key) {
+        *value = new_value;
+      }
+    }
+
+    match lua.get::<String, _>("__maat_errors") {
+      Some(errors) => errors.lines().map(|e| e.to_string()).collect(),
+      None => Vec::new(),
+    }
+  }
+}
+
+// A value from a script's params table, set per object in the inspector
+#[derive(Clone)]
+pub struct ScriptParam {
+  pub name: String,
+  pub value: VariableValue,
+}
+
+impl ScriptParam {
+  pub fn new(name: String, value: VariableValue) -> ScriptParam {
+    ScriptParam {
+      name,
+      value,
+    }
+  }
+}
+
+// Built by SceneData each frame, keys and buttons are by name
+pub struct ScriptInput {
+  pub keys_held: Vec<String>,
+  pub keys_pressed: Vec<String>,
+  pub keys_released: Vec<String>,
+  pub mouse_position: Vector2<f32>,
+  // Left, right and middle
+  pub mouse_buttons: [bool; 3],
+  pub scroll: f32,
+  pub left_stick: Vector2<f32>,
+  pub right_stick: Vector2<f32>,
+  pub left_trigger: f32,
+  pub right_trigger: f32,
+  pub gamepad_buttons: Vec<String>,
+}
+
+impl ScriptInput {
+  pub fn new() -> ScriptInput {
+    ScriptInput {
+      keys_held: Vec::new(),
+      keys_pressed: Vec::new(),
+      keys_released: Vec::new(),
+      mouse_position: Vector2::new(0.0, 0.0),
+      mouse_buttons: [false; 3],
+      scroll: 0.0,
+      left_stick: Vector2::new(0.0, 0.0),
+      right_stick: Vector2::new(0.0, 0.0),
+      left_trigger: 0.0,
+      right_trigger: 0.0,
+      gamepad_buttons: Vec::new(),
+    }
+  }
+}
+
+pub fn install(lua: &mut Lua) -> Option<String> {
+  lua.openlibs();
+
+  let mut key_names = keymap::key_names();
+  key_names.extend(MODIFIER_NAMES.iter());
+  lua.set("__maat_key_names", key_names.join(","));
+  lua.set("__maat_mouse_names", MOUSE_BUTTONS.join(","));
+  lua.set("__maat_button_names", GAMEPAD_BUTTONS.join(","));
+
+  crate::hlua_error!(lua.execute::<()>(PRELUDE))
+}
+
+// Every prelude function returns an error message, empty when it worked
+fn call_prelude(lua: &mut Lua, function: &str) -> Option<String> {
+  match lua.get(function) {
+    Some(function) => {
+      let mut function: hlua::LuaFunction<_> = function;
+      match function.call::<String>() {
+        Ok(message) => {
+          if message.len() > 0 {
+            Some(message)
+          } else {
+            None
+          }
+        },
+        result => crate::hlua_error!(result),
+      }
+    },
+    None => Some("Scripting was not set up".to_string()),
+  }
+}
+
+// Forgets every object and compiled script, spawned objects are numbered from next_id
+pub fn reset(lua: &mut Lua, next_id: u32, logs: &mut Logs) {
+  lua.set("__maat_next_id", next_id);
+  lua.set("__maat_scripts", project_file("Scripts"));
+  if let Some(e) = call_prelude(lua, "__maat_reset") {
+    logs.add_error(e);
+  }
+}
+
+// Called once a frame before any callbacks
+pub fn write_input(lua: &mut Lua, input: &ScriptInput, logs: &mut Logs) {
+  let mouse_held = MOUSE_BUTTONS.iter().zip(input.mouse_buttons.iter())
+                     .filter(|(_, held)| **held)
+                     .map(|(name, _)| *name)
+                     .collect::<Vec<&str>>();
+
+  lua.set("__maat_keys_held", input.keys_held.join(","));
+  lua.set("__maat_keys_pressed", input.keys_pressed.join(","));
+  lua.set("__maat_keys_released", input.keys_released.join(","));
+  lua.set("__maat_mouse_held", mouse_held.join(","));
+  lua.set("__maat_mouse_x", input.mouse_position.x);
+  lua.set("__maat_mouse_y", input.mouse_position.y);
+  lua.set("__maat_scroll", input.scroll);
+  lua.set("__maat_left_stick_x", input.left_stick.x);
+  lua.set("__maat_left_stick_y", input.left_stick.y);
+  lua.set("__maat_right_stick_x", input.right_stick.x);
+  lua.set("__maat_right_stick_y", input.right_stick.y);
+  lua.set("__maat_left_trigger", input.left_trigger);
+  lua.set("__maat_right_trigger", input.right_trigger);
+  lua.set("__maat_buttons_held", input.gamepad_buttons.join(","));
+
+  if let Some(e) = call_prelude(lua, "__maat_input") {
+    logs.add_error(e);
+  }
+}
+
+pub fn write_object(lua: &mut Lua, object: &mut ScriptObject) {
+  lua.set("__maat_id", object.id);
+  lua.set("__maat_name", object.name.to_string());
+  lua.set("__maat_model", object.model.to_string());
+  for (key, value) in object.fields() {
+    lua.set(key, *value);
+  }
+
+  call_prelude(lua, "__maat_write");
+}
+
+// Scene variables as set in the editor, called after reset and before any script is compiled
+pub fn write_variables(lua: &mut Lua, variables: &Vec<SceneVariable>, logs: &mut Logs) {
+  for variable in variables {
+    lua.set("__maat_variable_name", variable.name.to_string());
+    match &variable.value {
+      VariableValue::Number(value) => lua.set("__maat_variable_value", *value),
+      VariableValue::Bool(value) => lua.set("__maat_variable_value", *value),
+      VariableValue::Text(value) => lua.set("__maat_variable_value", value.to_string()),
+    }
+
+    if let Some(e) = call_prelude(lua, "__maat_set_variable") {
+      logs.add_error(e);
+    }
+  }
+}
+
+// Values a script set to the wrong type are reported and left unchanged
+pub fn read_object(lua: &mut Lua, object: &mut ScriptObject) -> Vec<String> {
+  lua.set("__maat_id", object.id);
+  for (key, value) in object.fields() {
+    lua.set(key, *value);
+  }
+
+  match call_prelude(lua, "__maat_read") {
+    Some(e) => vec!(e),
+    None => object.read_fields(lua),
+  }
+}
+
+// Stops scripts finding an object the editor removed
+pub fn remove_object(lua: &mut Lua, id: u32) {
+  lua.set("__maat_id", id);
+  call_prelude(lua, "__maat_remove");
+}
+
+pub struct CompiledScript {
+  pub callbacks: Vec<Callback>,
+  // Old style <object name>update and <object name>init functions standing in for update and init
+  pub legacy_callbacks: Vec<String>,
+}
+
+// Runs the script once in its own environment, returns the callbacks it defines.
+// The object has to be written first so the script can see self.
+// When reloading an error leaves the last version that compiled running.
+pub fn compile(lua: &mut Lua, id: u32, object_name: &str, chunk_name: &str, source: &str, reload: bool) -> Result<CompiledScript, String> {
+  lua.set("__maat_id", id);
+  compile_chunk(lua, object_name, chunk_name, source, reload)
+}
+
+pub fn compile_scene(lua: &mut Lua, chunk_name: &str, source: &str, reload: bool) -> Result<Vec<Callback>, String> {
+  lua.set("__maat_id", SCENE_SCRIPT_ID);
+  compile_chunk(lua, "", chunk_name, source, reload).map(|compiled| compiled.callbacks)
+}
+
+// __maat_id has to be set first, legacy_prefix is empty when there are no old style names to look for
+fn compile_chunk(lua: &mut Lua, legacy_prefix: &str, chunk_name: &str, source: &str, reload: bool) -> Result<CompiledScript, String> {
+  let names = CALLBACKS.iter().map(|callback| callback.name()).collect::<Vec<&str>>();
+  lua.set("__maat_legacy_prefix", legacy_prefix.to_string());
+  lua.set("__maat_reload", reload);
+  lua.set("__maat_chunk", chunk_name.to_string());
+  lua.set("__maat_source", source.to_string());
+  lua.set("__maat_callbacks", names.join(","));
+
+  if let Some(e) = call_prelude(lua, "__maat_compile") {
+    return Err(e);
+  }
+
+  let callbacks = match lua.get::<String, _>("__maat_defined") {
+    Some(defined) => defined.split(',').filter_map(|name| Callback::from_name(name)).collect(),
+    None => Vec::new(),
+  };
+  let legacy_callbacks = match lua.get::<String, _>("__maat_legacy") {
+    Some(legacy) => legacy.split(',').filter(|name| name.len() > 0).map(|name| name.to_string()).collect(),
+    None => Vec::new(),
+  };
+
+  Ok(CompiledScript {
+    callbacks,
+    legacy_callbacks,
+  })
+}
+
+// The params a script declares with their default values, in name order
+pub fn declared_params(lua: &mut Lua, chunk_name: &str, source: &str) -> Result<Vec<ScriptParam>, String> {
+  lua.set("__maat_params_limit", PARAMS_INSTRUCTION_LIMIT);
+  lua.set("__maat_chunk", chunk_name.to_string());
+  lua.set("__maat_source", source.to_string());
+
+  if let Some(e) = call_prelude(lua, "__maat_params") {
+    return Err(e);
+  }
+
+  let list = lua.get::<String, _>("__maat_param_list").unwrap_or("".to_string());
+  Ok(list.lines().filter_map(|line| {
+    let mut parts = line.splitn(3, '\t');
+    match (parts.next(), parts.next(), parts.next()) {
+      (Some(name), Some(type_name), Some(value)) => {
+        VariableValue::parse(type_name, value).map(|value| ScriptParam::new(name.to_string(), value))
+      },
+      _ => None,
+    }
+  }).collect())
+}
+
+// Called after compile so the object's values replace the defaults before init
+pub fn write_params(lua: &mut Lua, id: u32, params: &Vec<ScriptParam>) -> Option<String> {
+  for param in params {
+    lua.set("__maat_id", id);
+    lua.set("__maat_param_name", param.name.to_string());
+    match &param.value {
+      VariableValue::Number(value) => lua.set("__maat_param_value", *value),
+      VariableValue::Bool(value) => lua.set("__maat_param_value", *value),
+      VariableValue::Text(value) => lua.set("__maat_param_value", value.to_string()),
+    }
+
+    if let Some(e) = call_prelude(lua, "__maat_set_param") {
+      return Some(e);
+    }
+  }
+
+  None
+}
+
+// Calls a callback found by compile
+pub fn call(lua: &mut Lua, id: u32, callback: Callback, delta_time: f32) -> Option<String> {
+  lua.set("__maat_id", id);
+  call_chunk(lua, callback, delta_time)
+}
+
+pub fn call_scene(lua: &mut Lua, callback: Callback, delta_time: f32) -> Option<String> {
+  lua.set("__maat_id", SCENE_SCRIPT_ID);
+  call_chunk(lua, callback, delta_time)
+}
+
+fn call_chunk(lua: &mut Lua, callback: Callback, delta_time: f32) -> Option<String> {
+  lua.set("__maat_callback", callback.name());
+  lua.set("__maat_pass_delta_time", callback.takes_delta_time());
+  lua.set("__maat_delta_time", delta_time);
+
+  call_prelude(lua, "__maat_call")
+}
+
+// Objects created with scene.spawn since the last call
+pub fn take_spawned(lua: &mut Lua, logs: &mut Logs) -> Vec<ScriptObject> {
+  let mut spawned = Vec::new();
+
+  loop {
+    if let Some(e) = call_prelude(lua, "__maat_next_spawned") {
+      logs.add_error(e);
+      break;
+    }
+
+    if !lua.get::<bool, _>("__maat_found").unwrap_or(false) {
+      break;
+    }
+
+    let id = lua.get::<u32, _>("__maat_id");
+    let name = lua.get::<String, _>("__maat_name");
+    let model = lua.get::<String, _>("__maat_model");
+    if let (Some(id), Some(name), Some(model)) = (id, name, model) {
+      let zero = Vector3::new(0.0, 0.0, 0.0);
+      let mut object = ScriptObject {
+        id,
+        name,
+        model,
+        position: zero,
+        rotation: zero,
+        size: Vector3::new(1.0, 1.0, 1.0),
+        velocity: zero,
+        acceleration: zero,
+      };
+
+      for e in object.read_fields(lua) {
+        logs.add_error(e);
+      }
+      spawned.push(object);
+    }
+  }
+
+  spawned
+}
+
+// Ids passed to scene.destroy since the last call
+pub fn take_destroyed(lua: &mut Lua, logs: &mut Logs) -> Vec<u32> {
+  let mut destroyed = Vec::new();
+
+  loop {
+    if let Some(e) = call_prelude(lua, "__maat_next_destroyed") {
+      logs.add_error(e);
+      break;
+    }
+
+    if !lua.get::<bool, _>("__maat_found").unwrap_or(false) {
+      break;
+    }
+
+    if let Some(id) = lua.get::<u32, _>("__maat_id") {
+      destroyed.push(id);
+    }
+  }
+
+  destroyed
+}
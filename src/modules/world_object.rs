@@ -2,21 +2,29 @@ use maat_graphics::DrawCall;
 use maat_graphics::imgui::*;
 
 use crate::modules::Logs;
+use crate::modules::rotation;
+use crate::modules::rotation::{EulerOrder, EulerCache};
+use crate::modules::scripting;
+use crate::modules::scripting::{ScriptObject, ScriptParam, Callback, ModifiedTimes};
+use crate::modules::script_errors::ScriptErrors;
+use crate::modules::scene_script;
+use crate::modules::scene_browser::project_file;
+use crate::modules::script_templates::ScriptTemplate;
 
 use std::io::{Write, BufWriter};
 use std::fs::File;
 use std::fs;
 use std::fs::copy;
 use std::path::Path;
+use std::time::SystemTime;
 
-use hlua;
 use hlua::Lua;
 
 use open;
 
-use crate::cgmath::{Vector2, Vector3};
+use crate::cgmath::{Vector2, Vector3, Quaternion};
 
-const LOCATION: &str = "./Scenes/";
+const LOCATION: &str = "Scenes/";
 const OBJECTS: &str = "/Objects/";
 
 #[macro_export]
@@ -42,15 +50,15 @@ macro_rules! hlua_error {
 pub struct DefaultOptions {
   position: Vector3<f32>,
   size: Vector3<f32>,
-  rotation: Vector3<f32>,
+  orientation: Quaternion<f32>,
 }
 
 impl DefaultOptions {
-  pub fn new(position: Vector3<f32>, size: Vector3<f32>, rotation: Vector3<f32>) -> DefaultOptions {
+  pub fn new(position: Vector3<f32>, size: Vector3<f32>, orientation: Quaternion<f32>) -> DefaultOptions {
     DefaultOptions {
       position,
       size,
-      rotation,
+      orientation,
     }
   }
 }
@@ -63,7 +71,7 @@ pub struct WorldObject {
   directory: String,
   
   position: Vector3<f32>,
-  rotation: Vector3<f32>,
+  orientation: Quaternion<f32>,
   size: Vector3<f32>,
   velocity: Vector3<f32>,
   acceleration: Vector3<f32>,
@@ -71,9 +79,26 @@ pub struct WorldObject {
   position_edit: bool,
   size_edit: bool,
   rotation_edit: bool,
+  // Angles from the scene csv or a script, drawn as they are so a scene looks the same
+  // as when it was saved
+  render_euler: Option<EulerCache>,
+  // Angles last typed into the inspector, shown until the orientation changes elsewhere
+  inspector_euler: Option<EulerCache>,
+  // Template picked for Create Script
+  template_idx: i32,
   
+  // Has a script named after it in the scene's Objects folder
   has_script: bool,
-  update_function: Option<File>,
+  // Path of a script from the shared Scripts folder, used instead of the one named after it
+  assigned_script: Option<String>,
+  // Callbacks the compiled script defines, filled when run mode starts
+  script_callbacks: Vec<Callback>,
+  // When the loaded script was last changed, checked for hot reloading
+  script_modified: Option<SystemTime>,
+  // Values for the script's params table, saved with the scene
+  script_params: Vec<ScriptParam>,
+  // When the params were last read from the script while editing
+  params_modified: Option<SystemTime>,
   default_options: DefaultOptions,
   
   instanced_buffer: bool,
@@ -81,10 +106,16 @@ pub struct WorldObject {
 
 impl Clone for WorldObject {
   fn clone(&self) -> Self {
-    let mut obj = WorldObject::new_with_name(self.reference_num, self.name.to_string(), self.directory.to_string(), self.model.to_string(), self.location.to_string(), self.position, self.rotation, self.size);
-    if let Some(function) = &self.update_function {
-      obj.update_function = Some(function.try_clone().unwrap());
-    }
+    let mut obj = WorldObject::new_with_name(self.reference_num, self.name.to_string(), self.directory.to_string(), self.model.to_string(), self.location.to_string(), self.position, self.rotation(), self.size);
+    obj.orientation = self.orientation;
+    obj.default_options.orientation = self.orientation;
+    obj.render_euler = self.render_euler;
+    obj.inspector_euler = self.inspector_euler;
+    obj.assigned_script = self.assigned_script.clone();
+    obj.script_callbacks = self.script_callbacks.clone();
+    obj.script_modified = self.script_modified;
+    obj.script_params = self.script_params.clone();
+    obj.params_modified = self.params_modified;
     
     obj
   }
@@ -100,7 +131,7 @@ impl WorldObject {
       name: model.to_owned() + &reference_num.to_string(),
       
       position: Vector3::new(0.0, 0.0, 0.0),
-      rotation: Vector3::new(0.0, 0.0, 0.0),
+      orientation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
       size: Vector3::new(1.0, 1.0, 1.0),
       position_edit: false,
       velocity: Vector3::new(0.0, 0.0, 0.0),
@@ -108,21 +139,29 @@ impl WorldObject {
       
       size_edit: false,
       rotation_edit: false,
+      render_euler: None,
+      inspector_euler: None,
+      template_idx: 0,
       
       has_script: false,
-      update_function: None,
-      default_options: DefaultOptions::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0),Vector3::new(0.0, 0.0, 0.0)),
+      assigned_script: None,
+      script_callbacks: Vec::new(),
+      script_modified: None,
+      script_params: Vec::new(),
+      params_modified: None,
+      default_options: DefaultOptions::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0), Quaternion::new(1.0, 0.0, 0.0, 0.0)),
       
       instanced_buffer: false,
     }
   }
   
+  // rotation is in degrees using rotation::RENDER_ORDER, the same as the scene csv
   pub fn new_with_name(reference_num: u32, object_name: String, directory: String, model: String, location: String, position: Vector3<f32>, rotation: Vector3<f32>, size: Vector3<f32>) -> WorldObject {
-    let function = None;
+    let orientation = rotation::quaternion_from_euler(rotation, rotation::RENDER_ORDER);
     
     let file_name = object_name.to_owned() + ".lua";
     let mut has_script = false;
-    if let Ok(_) = File::open(&Path::new(&(LOCATION.to_owned() + &directory.to_string() + &OBJECTS.to_string() + &file_name))) {
+    if let Ok(_) = File::open(&Path::new(&(project_file(LOCATION) + &directory.to_string() + &OBJECTS.to_string() + &file_name))) {
       has_script = true;
     }
     
@@ -134,7 +173,7 @@ impl WorldObject {
       directory,
       
       position,
-      rotation,
+      orientation,
       size,
       velocity: Vector3::new(0.0, 0.0, 0.0),
       acceleration: Vector3::new(0.0, 0.0, 0.0),
@@ -142,9 +181,16 @@ impl WorldObject {
       position_edit: false,
       size_edit: false,
       rotation_edit: false,
+      render_euler: Some(EulerCache::new(rotation::RENDER_ORDER, rotation, orientation)),
+      inspector_euler: None,
+      template_idx: 0,
       has_script,
-      update_function: function,
-      default_options: DefaultOptions::new(position, size, rotation),
+      assigned_script: None,
+      script_callbacks: Vec::new(),
+      script_modified: None,
+      script_params: Vec::new(),
+      params_modified: None,
+      default_options: DefaultOptions::new(position, size, orientation),
       
       instanced_buffer: false,
     };
@@ -168,7 +214,25 @@ impl WorldObject {
     WorldObject::new_with_name(reference_num, object_name.to_string(), directory, model, location, position, rotation, size)
   }
   
-  pub fn create_script(&mut self, logs: &mut Logs) {
+  // Copy of the model and size, a script named after the object stays with the original
+  // but a shared script and its params are copied
+  pub fn copy_to(&self, reference_num: u32, position: Vector3<f32>, orientation: Quaternion<f32>) -> WorldObject {
+    let mut object = WorldObject::new_empty(reference_num, self.model.to_string(), self.location.to_string(), self.directory.to_string());
+    if self.assigned_script.is_some() {
+      object.assigned_script = self.assigned_script.clone();
+      object.script_params = self.script_params.clone();
+    }
+    object.position = position;
+    object.orientation = orientation;
+    object.size = self.size;
+    object.default_options = DefaultOptions::new(position, self.size, orientation);
+    object.instanced_buffer = self.instanced_buffer;
+    
+    object
+  }
+  
+  // The template goes under a comment listing what scripts can use
+  pub fn create_script(&mut self, template: Option<&ScriptTemplate>, logs: &mut Logs) {
     if self.has_script {
       return;
     }
@@ -176,52 +240,53 @@ impl WorldObject {
     let file_name = self.name.to_owned() + ".lua";
     
     // Create lua file
-    if let Err(e) = fs::create_dir_all(LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string()) {
+    if let Err(e) = fs::create_dir_all(project_file(LOCATION) + &self.directory.to_string() + &OBJECTS.to_string()) {
       logs.add_error(e.to_string());
     }
     
-    match File::create(LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + &file_name.to_string()) {
+    match File::create(project_file(LOCATION) + &self.directory.to_string() + &OBJECTS.to_string() + &file_name.to_string()) {
       Ok(f) => {
         let mut f = BufWriter::new(f);
       
-        let data = "-- ref_num
--- delta_time
--- mouse_x
--- mouse_y
--- left_mouse
--- right_mouse
+        let mut data = "-- self.id
+-- self.name
+-- self.position
+-- self.rotation (degrees)
+-- self.size
+-- self.velocity
+-- self.acceleration
+--   each has x, y and z
+
+-- scene.find_by_name(name)
+-- scene.find_by_id(id)
+-- scene.spawn(model, position, rotation, size)
+-- scene.destroy(object)
+-- for object in scene.objects() do end
+-- scene.variables, the Scene Variables from Scene Details
+
+-- input.key_held(name), input.key_pressed(name), input.key_released(name)
+--   names as in keymap.ini, e.g. \"W\", \"Space\", \"Left\", \"Shift\"
+-- input.mouse_held(button), input.mouse_pressed(button), input.mouse_released(button)
+--   \"left\", \"right\" or \"middle\"
+-- input.mouse_position(), input.scroll()
+-- input.gamepad_stick(side), input.gamepad_trigger(side)
+-- input.gamepad_held(button), input.gamepad_pressed(button), input.gamepad_released(button)
 -- window_dim_x
 -- window_dim_y
--- w_key
--- a_key
--- s_key
--- d_key
+-- require(\"name\") loads Scripts/name.lua, shared by every script
 
--- x
--- y
--- z
--- rot_x
--- rot_y
--- rot_z
--- size_x
--- size_y
--- size_z
--- vel_x
--- vel_y
--- vel_z
--- acc_x
--- acc_y
--- acc_z
+-- Numbers, bools and strings in a params table can be set for each object
+-- in the inspector, the values set there replace the script's before init
 
-function ".to_owned() + &self.name.to_string() + "update()
-  x = x + vel_x*delta_time;
-  y = y + vel_y*delta_time;
-  z = z + vel_z*delta_time;
-  
-  vel_x = vel_x + acc_x*delta_time*delta_time;
-  vel_y = vel_y + acc_y*delta_time*delta_time;
-  vel_z = vel_z + acc_z*delta_time*delta_time;
-end";
+-- init(), update(dt), fixed_update(dt) and on_destroy() are called when defined
+
+-- Older scripts used x, y, z, rot_x, size_x, vel_x, acc_x and so on, those globals
+-- still read and write self so don't use them as names for your own globals
+
+".to_owned();
+        if let Some(template) = template {
+          data.push_str(&template.source_for(&self.name));
+        }
         
         if let Err(e) = f.write_all(data.as_bytes()) {
           logs.add_error(e.to_string());
@@ -240,11 +305,13 @@ end";
     }
     
     let file_name = self.name.to_owned() + ".lua";
-    if let Err(e) = fs::remove_file(LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + &file_name.to_string()) {
+    if let Err(e) = fs::remove_file(project_file(LOCATION) + &self.directory.to_string() + &OBJECTS.to_string() + &file_name.to_string()) {
       logs.add_error(e.to_string());
     }
     self.has_script = false;
-    self.update_function = None;
+    self.script_callbacks.clear();
+    self.script_params.clear();
+    self.params_modified = None;
   }
   
   pub fn save_script(&mut self, directory: String, logs: &mut Logs) {
@@ -255,12 +322,12 @@ end";
     let file_name = self.name.to_owned() + ".lua";
     
     // Create lua folder
-    if let Err(e) = fs::create_dir_all(LOCATION.to_owned() + &directory.to_string() + &OBJECTS.to_string()) {
+    if let Err(e) = fs::create_dir_all(project_file(LOCATION) + &directory.to_string() + &OBJECTS.to_string()) {
       logs.add_error(e.to_string());
     }
     
-    let file_from = LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + &file_name.to_string();
-    let file_to = LOCATION.to_owned() + &directory.to_string() + &OBJECTS.to_string() + &file_name.to_string();
+    let file_from = project_file(LOCATION) + &self.directory.to_string() + &OBJECTS.to_string() + &file_name.to_string();
+    let file_to = project_file(LOCATION) + &directory.to_string() + &OBJECTS.to_string() + &file_name.to_string();
     
     if file_from.eq(&file_to) {
       return;
@@ -271,12 +338,127 @@ end";
     }
   }
   
-  pub fn load_script(&mut self) {
-    self.update_function = None;
+  // Compiles the script once, only the callbacks it defines are called while running
+  pub fn load_script(&mut self, lua: &mut Lua, errors: &mut ScriptErrors) {
+    self.script_callbacks.clear();
+    self.compile_script(lua, false, errors);
+  }
+  
+  // Recompiles the script if its file changed since it was loaded, the transform
+  // and anything the script stored are kept. Returns true if it was reloaded.
+  pub fn reload_changed_script(&mut self, lua: &mut Lua, times: &mut ModifiedTimes, errors: &mut ScriptErrors) -> bool {
+    if !self.has_script() {
+      return false;
+    }
     
-    let file_name = self.name.to_owned() + ".lua";
-    if let Ok(f) = File::open(&Path::new(&(LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + &file_name))) {
-      self.update_function = Some(f);
+    let modified = times.get(&self.script_file());
+    if modified.is_none() || modified == self.script_modified {
+      return false;
+    }
+    
+    self.compile_script(lua, true, errors)
+  }
+  
+  fn compile_script(&mut self, lua: &mut Lua, reload: bool, errors: &mut ScriptErrors) -> bool {
+    // Set even if it doesn't compile so a broken file isn't retried every frame
+    self.script_modified = fs::metadata(self.script_file()).and_then(|metadata| metadata.modified()).ok();
+    
+    let source = match fs::read_to_string(self.script_file()) {
+      Ok(source) => source,
+      Err(_) => {
+        return false;
+      }
+    };
+    
+    match scripting::compile(lua, self.reference_num, &self.name, &self.script_path(), &source, reload) {
+      Ok(compiled) => {
+        self.script_callbacks = compiled.callbacks;
+        for name in compiled.legacy_callbacks {
+          let callback = if name.ends_with("init") { "init()" } else { "update(dt)" };
+          errors.warn(self.reference_num, &self.name, &self.script_path(),
+                      name + " is deprecated, rename it to " + callback);
+        }
+        // Reloading runs params = { ... } again so the object's values go back in
+        if let Some(e) = scripting::write_params(lua, self.reference_num, &self.script_params) {
+          errors.add(self.reference_num, &self.name, &self.script_path(), e);
+        }
+        true
+      },
+      Err(e) => {
+        errors.add(self.reference_num, &self.name, &self.script_path(), e);
+        false
+      }
+    }
+  }
+  
+  // Reads the params the script declares when its file changes, values already set
+  // are kept if the param still has the same type and the rest use the script's default
+  pub fn refresh_script_params(&mut self, lua: &mut Lua, times: &mut ModifiedTimes, logs: &mut Logs) {
+    if !self.has_script() {
+      return;
+    }
+    
+    let modified = times.get(&self.script_file());
+    if modified.is_none() || modified == self.params_modified {
+      return;
+    }
+    self.params_modified = modified;
+    
+    let source = match fs::read_to_string(self.script_file()) {
+      Ok(source) => source,
+      Err(_) => {
+        return;
+      }
+    };
+    
+    match scripting::declared_params(lua, &self.script_path(), &source) {
+      Ok(declared) => {
+        let old_params = &self.script_params;
+        self.script_params = declared.into_iter().map(|mut param| {
+          if let Some(old) = old_params.iter().find(|old| old.name == param.name) {
+            if old.value.type_name() == param.value.type_name() {
+              param.value = old.value.clone();
+            }
+          }
+          param
+        }).collect();
+      },
+      Err(e) => {
+        logs.add_error("Script params of ".to_owned() + &self.name + ": " + &e);
+      }
+    }
+  }
+  
+  pub fn script_params(&self) -> &Vec<ScriptParam> {
+    &self.script_params
+  }
+  
+  pub fn add_script_param(&mut self, param: ScriptParam) {
+    self.script_params.push(param);
+  }
+  
+  pub fn assigned_script(&self) -> Option<String> {
+    self.assigned_script.clone()
+  }
+  
+  // Params the new script still declares keep their values once it is read
+  pub fn assign_script(&mut self, script: Option<String>) {
+    if script != self.assigned_script {
+      self.assigned_script = script;
+      self.script_callbacks.clear();
+      self.params_modified = None;
+    }
+  }
+  
+  fn script_file(&self) -> String {
+    project_file(&self.script_path())
+  }
+  
+  // From the project folder, shown in errors and kept short since Lua cuts long chunk names
+  fn script_path(&self) -> String {
+    match &self.assigned_script {
+      Some(script) => script.to_string(),
+      None => LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + &self.name.to_string() + ".lua",
     }
   }
   
@@ -286,6 +468,43 @@ end";
     }
   }
   
+  pub fn has_script(&self) -> bool {
+    self.has_script || self.assigned_script.is_some()
+  }
+  
+  // The script is the one named after the object rather than an assigned one
+  pub fn has_named_script(&self) -> bool {
+    self.has_script && self.assigned_script.is_none()
+  }
+  
+  // Called after the object was renamed, the script named after it is renamed too.
+  // Shared is true when other objects still use the old name so the file is copied instead.
+  // If a script already has the new name the old one is assigned by path.
+  pub fn rename_script(&mut self, old_name: &str, shared: bool, logs: &mut Logs) {
+    let old_path = LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + old_name + ".lua";
+    let old_file = project_file(&old_path);
+    let new_file = self.script_file();
+    if !Path::new(&old_file).exists() {
+      return;
+    }
+    
+    let result = if self.name.len() == 0 || Path::new(&new_file).exists() {
+      Err(None)
+    } else if shared {
+      copy(&old_file, &new_file).map(|_| ()).map_err(|e| Some(e))
+    } else {
+      fs::rename(&old_file, &new_file).map_err(|e| Some(e))
+    };
+    
+    if let Err(e) = result {
+      if let Some(e) = e {
+        logs.add_error(e.to_string());
+      }
+      self.assigned_script = Some(old_path);
+      self.has_script = false;
+    }
+  }
+  
   pub fn instanced_rendered(&self) -> bool {
     self.instanced_buffer
   }
@@ -318,82 +537,104 @@ end";
     self.size
   }
   
+  // Euler angles in degrees using rotation::RENDER_ORDER
   pub fn rotation(&self) -> Vector3<f32> {
-    self.rotation
+    rotation::cached_euler(&self.render_euler, self.orientation, rotation::RENDER_ORDER)
+  }
+  
+  pub fn orientation(&self) -> Quaternion<f32> {
+    self.orientation
   }
   
   pub fn set_position(&mut self, pos: Vector3<f32>) {
     self.default_options.position = pos;
   }
   
+  // Unlike set_position this also moves objects that are not being edited
+  pub fn move_to(&mut self, pos: Vector3<f32>) {
+    self.position = pos;
+    self.default_options.position = pos;
+  }
+  
+  pub fn rotate_to(&mut self, orientation: Quaternion<f32>) {
+    self.orientation = orientation;
+    self.default_options.orientation = orientation;
+  }
+  
   pub fn reset(&mut self) {
     self.position = self.default_options.position;
     self.size = self.default_options.size;
-    self.rotation = self.default_options.rotation;
+    self.orientation = self.default_options.orientation;
   }
   
-  pub fn update_game(&mut self, lua: &mut Option<&mut Lua>, logs: &mut Logs) {
-    if self.update_function.is_none() {
+  // Created by scene.spawn, exists until run mode ends
+  pub fn from_script_object(object: ScriptObject, location: String, directory: String) -> WorldObject {
+    let mut world_object = WorldObject::new_with_name(object.id, object.name.to_string(), directory, object.model.to_string(), location,
+                                                      object.position, object.rotation, object.size);
+    world_object.velocity = object.velocity;
+    world_object.acceleration = object.acceleration;
+    
+    world_object
+  }
+  
+  fn script_object(&self) -> ScriptObject {
+    ScriptObject {
+      id: self.reference_num,
+      name: self.name.to_string(),
+      model: self.model.to_string(),
+      position: self.position,
+      rotation: self.rotation(),
+      size: self.size,
+      velocity: self.velocity,
+      acceleration: self.acceleration,
+    }
+  }
+  
+  fn apply_script_object(&mut self, object: ScriptObject) {
+    self.position = object.position;
+    self.size = object.size;
+    // Only convert when the script turned the object, keeps it from drifting
+    if object.rotation != self.rotation() {
+      self.orientation = rotation::quaternion_from_euler(object.rotation, rotation::RENDER_ORDER);
+      self.render_euler = Some(EulerCache::new(rotation::RENDER_ORDER, object.rotation, self.orientation));
+    }
+    self.velocity = object.velocity;
+    self.acceleration = object.acceleration;
+  }
+  
+  // Does nothing when the script doesn't define the callback
+  pub fn call_script(&mut self, lua: &mut Lua, callback: Callback, delta_time: f32, errors: &mut ScriptErrors) {
+    if !self.script_callbacks.contains(&callback) {
       return;
     }
     
-    if let Some(lua) = lua {
-      lua.set("ref_num", self.reference_num);
-      lua.set("x", self.position.x);
-      lua.set("y", self.position.y);
-      lua.set("z", self.position.z);
-      lua.set("size_x", self.size.x);
-      lua.set("size_y", self.size.y);
-      lua.set("size_z", self.size.z);
-      lua.set("rot_x", self.rotation.x);
-      lua.set("rot_y", self.rotation.y);
-      lua.set("rot_z", self.rotation.z);
-      lua.set("vel_x", self.velocity.x);
-      lua.set("vel_y", self.velocity.y);
-      lua.set("vel_z", self.velocity.z);
-      lua.set("acc_x", self.acceleration.x);
-      lua.set("acc_y", self.acceleration.y);
-      lua.set("acc_z", self.acceleration.z);
-      
-      if let Some(function) = &self.update_function {
-        let reader_result = lua.execute_from_reader::<(), _>(function);
-        if let Some(e) = hlua_error!(reader_result) {
-          logs.add_error(e.to_string());
-        }
-        
-        let function_name = self.name.to_owned() + "update";
-       
-        if let Some(update) = lua.get(function_name.to_string()) {
-          let mut update: hlua::LuaFunction<_> = update;
-          let result = update.call::<()>();
-          if let Some(e) = hlua_error!(result) {
-            logs.add_error(e.to_string());
-          }
-        }
-      }
-      
-      self.position.x = lua.get("x").unwrap();
-      self.position.y = lua.get("y").unwrap();
-      self.position.z = lua.get("z").unwrap();
-      self.size.x = lua.get("size_x").unwrap();
-      self.size.y = lua.get("size_y").unwrap();
-      self.size.z = lua.get("size_z").unwrap();
-      self.rotation.x = lua.get("rot_x").unwrap();
-      self.rotation.y = lua.get("rot_y").unwrap();
-      self.rotation.z = lua.get("rot_z").unwrap();
-      self.velocity.x = lua.get("vel_x").unwrap();
-      self.velocity.y = lua.get("vel_y").unwrap();
-      self.velocity.z = lua.get("vel_z").unwrap();
-      self.acceleration.x = lua.get("acc_x").unwrap();
-      self.acceleration.y = lua.get("acc_y").unwrap();
-      self.acceleration.z = lua.get("acc_z").unwrap();
-    }
-  }
-  
-  pub fn update(&mut self, ui: Option<&Ui>, instanced_buffers: &Vec<String>, window_dim: Vector2<f32>, _delta_time: f32, logs: &mut Logs) {
+    if let Some(e) = scripting::call(lua, self.reference_num, callback, delta_time) {
+      errors.add(self.reference_num, &self.name, &self.script_path(), e);
+    }
+  }
+  
+  // Lets scripts see the object, needed before its script is loaded
+  pub fn write_script_state(&self, lua: &mut Lua) {
+    scripting::write_object(lua, &mut self.script_object());
+  }
+  
+  // Picks up what scripts did to the object this frame
+  pub fn read_script_state(&mut self, lua: &mut Lua, errors: &mut ScriptErrors) {
+    let mut object = self.script_object();
+    for e in scripting::read_object(lua, &mut object) {
+      errors.add(self.reference_num, &self.name, &self.script_path(), e);
+    }
+    self.apply_script_object(object);
+  }
+  
+  // scripts are the shared scripts that can be assigned, paths from the project folder
+  pub fn update(&mut self, ui: Option<&Ui>, instanced_buffers: &Vec<String>, scripts: &Vec<String>, templates: &Vec<ScriptTemplate>, euler_order: EulerOrder, window_dim: Vector2<f32>, _delta_time: f32, logs: &mut Logs) {
      self.position = self.default_options.position;
      self.size = self.default_options.size;
-     self.rotation = self.default_options.rotation;
+     self.orientation = self.default_options.orientation;
+     
+     let mut euler = rotation::cached_euler(&self.inspector_euler, self.orientation, euler_order);
+     let mut euler_changed = false;
      
      let show_instanced_option = instanced_buffers.contains(&self.model.to_string());
      
@@ -403,31 +644,70 @@ end";
        let mut imstr_name = ImString::with_capacity(32);
        imstr_name.push_str(&self.name);
        
+       // First entry is the script named after the object
+       let mut script_names = vec!(ImString::new("Named after object"));
+       script_names.extend(scripts.iter().map(|script| ImString::new(script.to_string())));
+       let script_refs = script_names.iter().map(|name| name.as_ref()).collect::<Vec<&ImStr>>();
+       let mut script_idx = match &self.assigned_script {
+         Some(assigned) => scripts.iter().position(|script| script == assigned).map(|idx| idx as i32+1).unwrap_or(0),
+         None => 0,
+       };
+       let mut assign = None;
+       
+       let template_names = templates.iter().map(|template| ImString::new(template.name.replace("_", " "))).collect::<Vec<ImString>>();
+       let template_refs = template_names.iter().map(|name| name.as_ref()).collect::<Vec<&ImStr>>();
+       
        ui.window(im_str!("Object Being Placed"))
-       .size(ui_window_size, Condition::Appearing)
-       .position([window_dim.x-ui_window_size[0]-20.0, 432.0], Condition::Appearing)
+       .size(ui_window_size, Condition::FirstUseEver)
+       .position([window_dim.x-ui_window_size[0]-20.0, 432.0], Condition::FirstUseEver)
        //.always_auto_resize(true)
        .build(|| {
-          if self.has_script {
-            let txt = "Script: ".to_owned() + &self.name.to_string() + ".lua";
+          if scripts.len() > 0 {
+            ui.text("Script:");
+            ui.same_line(0.0);
+            ui.push_item_width(200.0);
+            if ui.combo(im_str!("##assigned_script"), &mut script_idx, &script_refs, -1) {
+              assign = Some(if script_idx > 0 { Some(scripts[script_idx as usize-1].to_string()) } else { None });
+            }
+            ui.push_item_width(0.0);
+          }
+          if self.has_script() {
+            let txt = "Script: ".to_owned() + &self.script_path();
             let mut imstr_script = ImString::with_capacity(32);
             imstr_script.push_str(&txt);
             ui.text(imstr_script);
             ui.same_line(0.0);
             if ui.button(im_str!("Open"), [0.0, 0.0]) {
-              let file_name = self.name.to_owned() + ".lua";
-               
-              let file = LOCATION.to_owned() + &self.directory.to_string() + &OBJECTS.to_string() + &file_name;
-              if let Err(e) = open::that(file) {
+              if let Err(e) = open::that(self.script_file()) {
                 logs.add_error(e.to_string());
               }
             }
-            if ui.button(im_str!("Delete Script"), [0.0, 0.0]) {
+            if self.assigned_script.is_some() {
+              if ui.button(im_str!("Unassign Script"), [0.0, 0.0]) {
+                assign = Some(None);
+              }
+            } else if ui.button(im_str!("Delete Script"), [0.0, 0.0]) {
               self.delete_script(logs);
             }
+            if self.script_params.len() > 0 {
+              ui.text("Params:");
+              for i in 0..self.script_params.len() {
+                let param = &mut self.script_params[i];
+                ui.push_item_width(150.0);
+                scene_script::edit_value(ui, &im_str!("{}##param{}", param.name, i), &mut param.value);
+                ui.push_item_width(0.0);
+              }
+            }
           } else {
+            if templates.len() > 0 {
+              ui.push_item_width(150.0);
+              ui.combo(im_str!("##script_template"), &mut self.template_idx, &template_refs, -1);
+              ui.push_item_width(0.0);
+              ui.same_line(0.0);
+            }
             if ui.button(im_str!("Create Script"), [0.0, 0.0]) {
-              self.create_script(logs);
+              let template = templates.get(self.template_idx.max(0) as usize);
+              self.create_script(template, logs);
             }
           }
           ui.text("Name:");
@@ -446,10 +726,11 @@ end";
          ui.same_line(0.0);
          ui.checkbox(im_str!("Edit"), &mut self.position_edit);
          ui.text(im_str!(
-            "Rotation: ({:.1},{:.1},{:.1})",
-            self.rotation.x,
-            self.rotation.y,
-            self.rotation.z,
+            "Rotation {} deg: ({:.1},{:.1},{:.1})",
+            euler_order.name(),
+            euler.x,
+            euler.y,
+            euler.z,
          ));
          ui.same_line(0.0);
          ui.checkbox(im_str!("Edit##1"), &mut self.rotation_edit);
@@ -489,15 +770,15 @@ end";
            ui.next_column();
            ui.text(im_str!("X:"));
            ui.same_line(0.0);
-           ui.input_float(im_str!("##rotx"), &mut self.rotation.x).build();
+           euler_changed |= ui.input_float(im_str!("##rotx"), &mut euler.x).build();
            ui.next_column();
            ui.text(im_str!("Y:"));
            ui.same_line(0.0);
-           ui.input_float(im_str!("##roty"), &mut self.rotation.y).build();
+           euler_changed |= ui.input_float(im_str!("##roty"), &mut euler.y).build();
            ui.next_column();
            ui.text(im_str!("Z:"));
            ui.same_line(0.0);
-           ui.input_float(im_str!("##rotz"), &mut self.rotation.z).build();
+           euler_changed |= ui.input_float(im_str!("##rotz"), &mut euler.z).build();
          }
          if self.size_edit {
            loop {
@@ -524,19 +805,32 @@ end";
         
       });
       
+      if let Some(script) = assign {
+        self.assign_script(script);
+      }
+      
+      // The editor moves a script named after the object with rename_script
       self.name = imstr_name.to_str().to_string();
       
+      if euler_changed {
+        self.orientation = rotation::quaternion_from_euler(euler, euler_order);
+        self.inspector_euler = Some(EulerCache::new(euler_order, euler, self.orientation));
+        if euler_order == rotation::RENDER_ORDER {
+          self.render_euler = self.inspector_euler;
+        }
+      }
+      
       self.default_options.position = self.position;
       self.default_options.size = self.size;
-      self.default_options.rotation = self.rotation;
+      self.default_options.orientation = self.orientation;
     }
   }
   
   pub fn draw_hologram(&self, draw_calls: &mut Vec<DrawCall>) {
     if self.instanced_buffer {
-      draw_calls.push(DrawCall::add_instanced_hologram_model(self.model.to_string(), self.position, self.size, self.rotation));
+      draw_calls.push(DrawCall::add_instanced_hologram_model(self.model.to_string(), self.position, self.size, self.rotation()));
     } else {
-      draw_calls.push(DrawCall::draw_hologram_model(self.position, self.size, self.rotation, self.model.to_string()));
+      draw_calls.push(DrawCall::draw_hologram_model(self.position, self.size, self.rotation(), self.model.to_string()));
     }
   }
   
@@ -545,11 +839,11 @@ end";
        draw_calls.push(DrawCall::add_instanced_model(self.model.to_string(), 
                                                      self.position,
                                                      self.size,
-                                                     self.rotation));
+                                                     self.rotation()));
     } else {
       draw_calls.push(DrawCall::draw_model(self.position,
                                            self.size,
-                                           self.rotation,
+                                           self.rotation(),
                                            self.model.to_string()));
     }
   }